@@ -1,6 +1,8 @@
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::redact;
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("authentication failed")]
@@ -48,17 +50,18 @@ impl AppError {
             AppError::Unknown(_) => ("UNKNOWN", false),
         };
 
+        // Messages are redacted when errors are created; this catches any
+        // that were built from text some other way.
         AppErrorPayload {
             code: code.to_string(),
-            message: self.to_string(),
+            message: redact::redact_text(&self.to_string()),
             recoverable,
         }
     }
 
     pub fn to_json(&self) -> String {
         serde_json::to_string(&self.payload()).unwrap_or_else(|_| {
-            "{\"code\":\"UNKNOWN\",\"message\":\"unknown error\",\"recoverable\":false}"
-                .to_string()
+            "{\"code\":\"UNKNOWN\",\"message\":\"unknown error\",\"recoverable\":false}".to_string()
         })
     }
 }
@@ -83,12 +86,18 @@ impl From<keyring::Error> for AppError {
     }
 }
 
+// reqwest includes the request URL, and with it the API key, in its messages.
 impl From<reqwest::Error> for AppError {
-    fn from(value: reqwest::Error) -> Self {
+    fn from(mut value: reqwest::Error) -> Self {
+        if let Some(url) = value.url_mut() {
+            redact::redact_url(url);
+        }
+        let message = redact::redact_text(&value.to_string());
+
         if value.is_connect() || value.is_timeout() || value.is_request() {
-            return AppError::Network(value.to_string());
+            return AppError::Network(message);
         }
 
-        AppError::Unknown(value.to_string())
+        AppError::Unknown(message)
     }
 }
@@ -1,12 +1,20 @@
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use reqwest::blocking::{Client, Response};
+use reqwest::blocking::{Client, RequestBuilder, Response};
 use reqwest::StatusCode;
 use serde::Deserialize;
 
 use crate::app_error::{AppError, AppResult};
-use crate::models::{IssueDetail, IssueSummary, Project};
+use crate::markdown::{self, ConvertOptions};
+use crate::models::{
+    Attachment, Comment, FilterOptions, IssueDetail, IssueFacets, IssueFilter, IssueListing,
+    IssueSort, IssueSummary, NamedId, Project, SortOrder, WikiPage, WikiSummary,
+};
+use crate::oauth::{self, OAuthCredentials, SharedCredentials};
+use crate::redact;
+use crate::throttle::Throttle;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -14,38 +22,150 @@ struct BacklogProject {
     id: i64,
     project_key: String,
     name: String,
+    text_formatting_rule: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct BacklogIssue {
+    project_id: Option<i64>,
     issue_key: String,
     summary: String,
     description: Option<String>,
     updated: String,
+    created: Option<String>,
+    issue_type: Option<BacklogNamed>,
+    status: Option<BacklogNamed>,
+    priority: Option<BacklogNamed>,
+    assignee: Option<BacklogUser>,
+    created_user: Option<BacklogUser>,
+    due_date: Option<String>,
+    parent_issue_id: Option<i64>,
+    #[serde(default)]
+    category: Vec<BacklogNamed>,
+    #[serde(default)]
+    versions: Vec<BacklogNamed>,
+    #[serde(default)]
+    milestone: Vec<BacklogNamed>,
 }
 
+#[derive(Debug, Deserialize)]
+struct BacklogCount {
+    count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogNamed {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogComment {
+    id: i64,
+    content: Option<String>,
+    created_user: Option<BacklogUser>,
+    created: String,
+    updated: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogUser {
+    id: Option<i64>,
+    name: String,
+}
+
+// `/wikis` leaves out `content`; `/wikis/:id` includes it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogWiki {
+    id: i64,
+    project_id: i64,
+    name: String,
+    content: Option<String>,
+    #[serde(default)]
+    tags: Vec<BacklogNamed>,
+    updated: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BacklogAttachment {
+    id: i64,
+    name: String,
+    size: i64,
+}
+
+// The largest page `/comments` returns.
+const COMMENT_PAGE_SIZE: usize = 100;
+// The most issues `/api/v2/issues` returns per request.
+pub const ISSUE_PAGE_SIZE: usize = 100;
+
 pub struct BacklogClient {
     base_url: String,
-    api_key: String,
+    auth: Auth,
+    projects: Vec<Project>,
+    throttle: Arc<Throttle>,
     client: Client,
 }
 
+// Stores rotated tokens; the old refresh token is dead once a new one has
+// been issued.
+type RefreshHook = Box<dyn Fn(&OAuthCredentials) -> AppResult<()> + Send + Sync>;
+
+enum Auth {
+    ApiKey(String),
+    OAuth {
+        credentials: SharedCredentials,
+        on_refresh: RefreshHook,
+    },
+}
+
 impl BacklogClient {
     pub fn new(base_url: &str, api_key: &str) -> AppResult<Self> {
-        let normalized_base_url = base_url.trim();
         let normalized_api_key = api_key.trim();
+        if normalized_api_key.is_empty() {
+            return Err(AppError::Validation("API key is required".to_string()));
+        }
+        Self::build(base_url, Auth::ApiKey(normalized_api_key.to_string()))
+    }
 
+    pub fn with_oauth(
+        base_url: &str,
+        credentials: OAuthCredentials,
+        on_refresh: impl Fn(&OAuthCredentials) -> AppResult<()> + Send + Sync + 'static,
+    ) -> AppResult<Self> {
+        Self::with_shared_oauth(base_url, Arc::new(Mutex::new(credentials)), on_refresh)
+    }
+
+    pub fn with_shared_oauth(
+        base_url: &str,
+        credentials: SharedCredentials,
+        on_refresh: impl Fn(&OAuthCredentials) -> AppResult<()> + Send + Sync + 'static,
+    ) -> AppResult<Self> {
+        Self::build(
+            base_url,
+            Auth::OAuth {
+                credentials,
+                on_refresh: Box::new(on_refresh),
+            },
+        )
+    }
+
+    fn build(base_url: &str, auth: Auth) -> AppResult<Self> {
+        let normalized_base_url = base_url.trim();
         if normalized_base_url.is_empty() {
             return Err(AppError::Validation("space URL is required".to_string()));
         }
-        if normalized_api_key.is_empty() {
-            return Err(AppError::Validation("API key is required".to_string()));
-        }
 
         Ok(Self {
             base_url: normalized_base_url.trim_end_matches('/').to_string(),
-            api_key: normalized_api_key.to_string(),
+            auth,
+            projects: Vec::new(),
+            throttle: Arc::default(),
             client: Client::builder()
                 .connect_timeout(Duration::from_secs(8))
                 .timeout(Duration::from_secs(20))
@@ -53,105 +173,492 @@ impl BacklogClient {
         })
     }
 
-    pub fn verify_connection(&self) -> AppResult<()> {
-        let url = self.url_with_key("/api/v2/users/myself");
-        let response = self.client.get(url).send()?;
-        map_status(response).map(|_| ())
+    // Known projects decide how descriptions are converted: their keys are
+    // linked as issue mentions and their formatting rule picks the parser.
+    pub fn with_projects(mut self, projects: Vec<Project>) -> Self {
+        self.projects = projects;
+        self
     }
 
-    pub fn fetch_projects(&self) -> AppResult<Vec<Project>> {
-        let url = self.url_with_key("/api/v2/projects");
-        let response = self.get_with_retry(&url)?;
-        let payload: Vec<BacklogProject> = response.json().map_err(AppError::from)?;
-
-        let now = chrono::Utc::now().to_rfc3339();
-        Ok(payload
-            .into_iter()
-            .map(|p| Project {
-                id: p.id,
-                project_key: p.project_key,
-                name: p.name,
-                synced_at: now.clone(),
-            })
-            .collect())
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
     }
 
-    pub fn fetch_issue_by_key(&self, issue_key: &str) -> AppResult<IssueDetail> {
-        let path = format!("/api/v2/issues/{issue_key}");
-        let url = self.url_with_key(&path);
-        let response = self.get_with_retry(&url)?;
-        let issue: BacklogIssue = response.json().map_err(AppError::from)?;
-
-        Ok(self.to_detail(issue))
-    }
-
-    pub fn search_issues_by_keyword(&self, keyword: &str) -> AppResult<Vec<IssueSummary>> {
-        let path = format!(
-            "/api/v2/issues?keyword={}",
-            urlencoding::encode(keyword.trim())
-        );
-        let url = self.url_with_key(&path);
-        let response = self.get_with_retry(&url)?;
-        let items: Vec<BacklogIssue> = response.json().map_err(AppError::from)?;
-        Ok(items
-            .into_iter()
-            .map(|issue| IssueSummary {
-                issue_key: issue.issue_key,
-                summary: issue.summary,
-                updated_at: issue.updated,
-            })
-            .collect())
+    // Issues only carry the parent's id. A parent the API key cannot see is
+    // left out rather than failing the whole fetch.
+    fn fetch_parent_issue_key(&self, parent_id: i64) -> AppResult<Option<String>> {
+        let url = self.api_url(&format!("/api/v2/issues/{parent_id}"));
+        match self.get_with_retry(&url) {
+            Ok(response) => {
+                let parent: BacklogIssue = response.json().map_err(AppError::from)?;
+                Ok(Some(parent.issue_key))
+            }
+            Err(AppError::NotFound) | Err(AppError::Forbidden) => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
     fn to_detail(&self, issue: BacklogIssue) -> IssueDetail {
+        let facets = facets_of(&issue);
         let raw = issue.description.unwrap_or_default();
-        let md = crate::markdown::backlog_to_markdown(&raw);
+        let options = ConvertOptions::for_issue(&self.base_url, &issue.issue_key, &self.projects);
+        let conversion = markdown::convert(&raw, &options);
         IssueDetail {
             issue_key: issue.issue_key,
             summary: issue.summary,
             description_raw: raw,
-            description_md: md,
+            description_md: conversion.markdown,
             updated_at: issue.updated,
             synced_at: chrono::Utc::now().to_rfc3339(),
+            conversion_warnings: conversion.warnings,
+            issue_type: issue.issue_type.map(|t| t.name),
+            status: issue.status.map(|s| s.name),
+            priority: issue.priority.map(|p| p.name),
+            assignee: issue.assignee.map(|u| u.name),
+            created_user: issue.created_user.map(|u| u.name),
+            created_at: issue.created,
+            due_date: issue.due_date,
+            parent_issue_key: None,
+            categories: names(issue.category),
+            versions: names(issue.versions),
+            milestones: names(issue.milestone),
+            facets,
         }
     }
 
-    fn url_with_key(&self, path: &str) -> String {
+    // API keys go in the query string; OAuth tokens in a header (see `get`).
+    fn api_url(&self, path: &str) -> String {
+        let Auth::ApiKey(api_key) = &self.auth else {
+            return format!("{}{}", self.base_url, path);
+        };
         let connector = if path.contains('?') { '&' } else { '?' };
-        let encoded_key = urlencoding::encode(&self.api_key);
-        format!("{}{}{}apiKey={}", self.base_url, path, connector, encoded_key)
+        let encoded_key = urlencoding::encode(api_key);
+        format!(
+            "{}{}{}apiKey={}",
+            self.base_url, path, connector, encoded_key
+        )
+    }
+
+    fn get(&self, url: &str) -> AppResult<RequestBuilder> {
+        let request = self.client.get(url);
+        match &self.auth {
+            Auth::ApiKey(_) => Ok(request),
+            Auth::OAuth { credentials, .. } => {
+                let credentials = credentials.lock().map_err(|_| token_lock_error())?;
+                Ok(request.bearer_auth(&credentials.tokens.access_token))
+            }
+        }
+    }
+
+    // Access tokens expire after an hour. A 401 with OAuth refreshes the
+    // token once and repeats the request; a second 401 stands.
+    fn with_token_refresh<T>(&self, request: impl Fn() -> AppResult<T>) -> AppResult<T> {
+        let used = self.access_token()?;
+        match request() {
+            Err(AppError::AuthInvalid) if self.refresh_access_token(used.as_deref())? => request(),
+            result => result,
+        }
+    }
+
+    fn access_token(&self) -> AppResult<Option<String>> {
+        match &self.auth {
+            Auth::ApiKey(_) => Ok(None),
+            Auth::OAuth { credentials, .. } => {
+                let credentials = credentials.lock().map_err(|_| token_lock_error())?;
+                Ok(Some(credentials.tokens.access_token.clone()))
+            }
+        }
+    }
+
+    // Refreshes under the lock of the shared credentials. Another client may
+    // have refreshed since `failed` was sent; its tokens are used as they are,
+    // as the refresh token this client knew of no longer works.
+    fn refresh_access_token(&self, failed: Option<&str>) -> AppResult<bool> {
+        let Auth::OAuth {
+            credentials,
+            on_refresh,
+        } = &self.auth
+        else {
+            return Ok(false);
+        };
+        let mut credentials = credentials.lock().map_err(|_| token_lock_error())?;
+        if failed.is_some_and(|failed| failed != credentials.tokens.access_token) {
+            return Ok(true);
+        }
+        let Some(refresh_token) = credentials.tokens.refresh_token.clone() else {
+            return Ok(false);
+        };
+
+        credentials.tokens = oauth::refresh_tokens(
+            &self.client,
+            &self.base_url,
+            &credentials.app,
+            &refresh_token,
+        )?;
+        on_refresh(&credentials)?;
+        Ok(true)
     }
 
     fn get_with_retry(&self, url: &str) -> AppResult<Response> {
+        self.with_token_refresh(|| self.get_with_backoff(url))
+    }
+
+    // Network failures are retried with a short backoff. A 429 waits for the
+    // rate-limit window to reset and does not count against those attempts.
+    fn get_with_backoff(&self, url: &str) -> AppResult<Response> {
         let mut wait = 1;
         let max_attempts = 3;
+        let max_rate_limit_waits = 5;
+        let mut attempt = 1;
+        let mut rate_limit_waits = 0;
 
-        for attempt in 1..=max_attempts {
-            let resp = self.client.get(url).send();
-            match resp {
+        loop {
+            self.throttle.before_request();
+            match self.get(url)?.send() {
                 Ok(r) => {
-                    if r.status() == StatusCode::TOO_MANY_REQUESTS && attempt < max_attempts {
-                        thread::sleep(Duration::from_secs(wait));
-                        wait *= 2;
+                    self.throttle.observe(r.headers());
+                    if r.status() == StatusCode::TOO_MANY_REQUESTS
+                        && rate_limit_waits < max_rate_limit_waits
+                    {
+                        rate_limit_waits += 1;
+                        log::info!("rate limited: {}", redact::redact_text(url));
+                        self.throttle
+                            .after_rate_limit(Duration::from_secs(1 << rate_limit_waits));
                         continue;
                     }
                     return map_status(r);
                 }
                 Err(e) => {
                     if (e.is_timeout() || e.is_connect()) && attempt < max_attempts {
+                        log::info!("retrying in {wait}s: {}", AppError::from(e));
                         thread::sleep(Duration::from_secs(wait));
                         wait *= 2;
+                        attempt += 1;
                         continue;
                     }
                     return Err(e.into());
                 }
             }
         }
+    }
+}
+
+// The Backlog endpoints the commands use. Commands take any implementation,
+// so the online-first logic can run against a stand-in in tests.
+pub trait BacklogApi {
+    fn verify_connection(&self) -> AppResult<()>;
+    fn fetch_projects(&self) -> AppResult<Vec<Project>>;
+    fn fetch_issue_by_key(&self, issue_key: &str) -> AppResult<IssueDetail>;
+    fn fetch_comments(&self, issue_key: &str) -> AppResult<Vec<Comment>>;
+    fn fetch_attachments(&self, issue_key: &str) -> AppResult<Vec<Attachment>>;
+    fn download_attachment(&self, issue_key: &str, attachment_id: i64) -> AppResult<Vec<u8>>;
+    fn search_issues(
+        &self,
+        filter: &IssueFilter,
+        count: usize,
+        offset: usize,
+    ) -> AppResult<Vec<IssueListing>>;
+    fn count_issues(&self, filter: &IssueFilter) -> AppResult<u64>;
+    fn fetch_filter_options(&self, project_id: i64) -> AppResult<FilterOptions>;
+    fn fetch_wikis(&self, project_key: &str) -> AppResult<Vec<WikiSummary>>;
+    fn fetch_wiki(&self, wiki_id: i64) -> AppResult<WikiPage>;
+
+    // Every issue matching the filter, oldest first whatever the filter's
+    // sort, so pages do not shift while new issues are created.
+    fn list_all_issues(&self, filter: &IssueFilter) -> AppResult<Vec<IssueListing>> {
+        let filter = IssueFilter {
+            sort: IssueSort::Created,
+            order: SortOrder::Asc,
+            ..filter.clone()
+        };
+        let mut issues = Vec::new();
+        loop {
+            let page = self.search_issues(&filter, ISSUE_PAGE_SIZE, issues.len())?;
+            let page_len = page.len();
+            issues.extend(page);
 
-        Err(AppError::Unknown("retry loop exhausted".to_string()))
+            if page_len < ISSUE_PAGE_SIZE {
+                return Ok(issues);
+            }
+        }
     }
 }
 
+impl BacklogApi for BacklogClient {
+    fn verify_connection(&self) -> AppResult<()> {
+        let url = self.api_url("/api/v2/users/myself");
+        self.get_with_retry(&url).map(|_| ())
+    }
+
+    fn fetch_projects(&self) -> AppResult<Vec<Project>> {
+        let url = self.api_url("/api/v2/projects");
+        let response = self.get_with_retry(&url)?;
+        let payload: Vec<BacklogProject> = response.json().map_err(AppError::from)?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        Ok(payload
+            .into_iter()
+            .map(|p| Project {
+                id: p.id,
+                project_key: p.project_key,
+                name: p.name,
+                text_formatting_rule: p
+                    .text_formatting_rule
+                    .unwrap_or_else(|| "backlog".to_string()),
+                synced_at: now.clone(),
+            })
+            .collect())
+    }
+
+    fn fetch_issue_by_key(&self, issue_key: &str) -> AppResult<IssueDetail> {
+        let path = format!("/api/v2/issues/{issue_key}");
+        let url = self.api_url(&path);
+        let response = self.get_with_retry(&url)?;
+        let issue: BacklogIssue = response.json().map_err(AppError::from)?;
+
+        let parent_issue_key = match issue.parent_issue_id {
+            Some(id) => self.fetch_parent_issue_key(id)?,
+            None => None,
+        };
+        Ok(IssueDetail {
+            parent_issue_key,
+            ..self.to_detail(issue)
+        })
+    }
+
+    // Pages through the comments oldest first. Comments without text (status
+    // or field changes only) are left out.
+    fn fetch_comments(&self, issue_key: &str) -> AppResult<Vec<Comment>> {
+        let options = ConvertOptions::for_issue(&self.base_url, issue_key, &self.projects);
+        let mut comments = Vec::new();
+        let mut last_id: Option<i64> = None;
+
+        loop {
+            let mut path =
+                format!("/api/v2/issues/{issue_key}/comments?order=asc&count={COMMENT_PAGE_SIZE}");
+            if let Some(id) = last_id {
+                path.push_str(&format!("&minId={id}"));
+            }
+            let url = self.api_url(&path);
+            let page: Vec<BacklogComment> =
+                self.get_with_retry(&url)?.json().map_err(AppError::from)?;
+            let page_len = page.len();
+
+            for c in page {
+                if last_id.is_some_and(|id| c.id <= id) {
+                    continue;
+                }
+                last_id = Some(c.id);
+                let raw = c.content.unwrap_or_default();
+                if raw.trim().is_empty() {
+                    continue;
+                }
+                comments.push(Comment {
+                    id: c.id,
+                    issue_key: issue_key.to_string(),
+                    author: c.created_user.map(|u| u.name).unwrap_or_default(),
+                    content_md: markdown::backlog_to_markdown(&raw, &options),
+                    content_raw: raw,
+                    created_at: c.created,
+                    updated_at: c.updated,
+                });
+            }
+
+            if page_len < COMMENT_PAGE_SIZE {
+                return Ok(comments);
+            }
+        }
+    }
+
+    fn fetch_attachments(&self, issue_key: &str) -> AppResult<Vec<Attachment>> {
+        let path = format!("/api/v2/issues/{issue_key}/attachments");
+        let url = self.api_url(&path);
+        let response = self.get_with_retry(&url)?;
+        let items: Vec<BacklogAttachment> = response.json().map_err(AppError::from)?;
+        Ok(items
+            .into_iter()
+            .map(|a| Attachment {
+                id: a.id,
+                name: a.name,
+                size: a.size,
+            })
+            .collect())
+    }
+
+    fn download_attachment(&self, issue_key: &str, attachment_id: i64) -> AppResult<Vec<u8>> {
+        let path = format!("/api/v2/issues/{issue_key}/attachments/{attachment_id}");
+        let url = self.api_url(&path);
+        let response = self.get_with_retry(&url)?;
+        Ok(response.bytes().map_err(AppError::from)?.to_vec())
+    }
+
+    fn search_issues(
+        &self,
+        filter: &IssueFilter,
+        count: usize,
+        offset: usize,
+    ) -> AppResult<Vec<IssueListing>> {
+        let path = format!(
+            "/api/v2/issues?{}&count={count}&offset={offset}",
+            issue_query(filter)
+        );
+        let url = self.api_url(&path);
+        let response = self.get_with_retry(&url)?;
+        let items: Vec<BacklogIssue> = response.json().map_err(AppError::from)?;
+        Ok(items.into_iter().map(to_listing).collect())
+    }
+
+    fn count_issues(&self, filter: &IssueFilter) -> AppResult<u64> {
+        let path = format!("/api/v2/issues/count?{}", issue_query(filter));
+        let url = self.api_url(&path);
+        let response = self.get_with_retry(&url)?;
+        let payload: BacklogCount = response.json().map_err(AppError::from)?;
+        Ok(payload.count)
+    }
+
+    fn fetch_filter_options(&self, project_id: i64) -> AppResult<FilterOptions> {
+        let fetch = |resource: &str| -> AppResult<Vec<NamedId>> {
+            let url = self.api_url(&format!("/api/v2/projects/{project_id}/{resource}"));
+            let items: Vec<BacklogNamed> =
+                self.get_with_retry(&url)?.json().map_err(AppError::from)?;
+            Ok(items
+                .into_iter()
+                .map(|item| NamedId {
+                    id: item.id,
+                    name: item.name,
+                })
+                .collect())
+        };
+
+        Ok(FilterOptions {
+            statuses: fetch("statuses")?,
+            assignees: fetch("users")?,
+            issue_types: fetch("issueTypes")?,
+            categories: fetch("categories")?,
+            milestones: fetch("versions")?,
+        })
+    }
+
+    fn fetch_wikis(&self, project_key: &str) -> AppResult<Vec<WikiSummary>> {
+        let path = format!(
+            "/api/v2/wikis?projectIdOrKey={}",
+            urlencoding::encode(project_key)
+        );
+        let url = self.api_url(&path);
+        let items: Vec<BacklogWiki> = self.get_with_retry(&url)?.json().map_err(AppError::from)?;
+        Ok(items
+            .into_iter()
+            .map(|w| WikiSummary {
+                id: w.id,
+                project_id: w.project_id,
+                name: w.name,
+                updated_at: w.updated,
+            })
+            .collect())
+    }
+
+    // Wiki text follows the formatting rule of the page's project, like
+    // issue descriptions do.
+    fn fetch_wiki(&self, wiki_id: i64) -> AppResult<WikiPage> {
+        let url = self.api_url(&format!("/api/v2/wikis/{wiki_id}"));
+        let wiki: BacklogWiki = self.get_with_retry(&url)?.json().map_err(AppError::from)?;
+
+        let project_key = self
+            .projects
+            .iter()
+            .find(|p| p.id == wiki.project_id)
+            .map(|p| p.project_key.as_str());
+        let options = ConvertOptions::for_project(&self.base_url, project_key, &self.projects);
+        let raw = wiki.content.unwrap_or_default();
+        let conversion = markdown::convert(&raw, &options);
+        Ok(WikiPage {
+            id: wiki.id,
+            project_id: wiki.project_id,
+            name: wiki.name,
+            content_raw: raw,
+            content_md: conversion.markdown,
+            tags: names(wiki.tags),
+            updated_at: wiki.updated,
+            synced_at: chrono::Utc::now().to_rfc3339(),
+            conversion_warnings: conversion.warnings,
+        })
+    }
+}
+
+// Query parameters shared by `/issues` and `/issues/count`.
+fn issue_query(filter: &IssueFilter) -> String {
+    let sort = match filter.sort {
+        IssueSort::Created => "created",
+        IssueSort::Updated => "updated",
+        IssueSort::DueDate => "dueDate",
+    };
+    let order = match filter.order {
+        SortOrder::Asc => "asc",
+        SortOrder::Desc => "desc",
+    };
+    let mut params = vec![format!("sort={sort}"), format!("order={order}")];
+
+    if let Some(keyword) = filter.keyword() {
+        params.push(format!("keyword={}", urlencoding::encode(keyword)));
+    }
+    let ids = [
+        ("projectId[]", &filter.project_ids),
+        ("statusId[]", &filter.status_ids),
+        ("assigneeId[]", &filter.assignee_ids),
+        ("issueTypeId[]", &filter.issue_type_ids),
+        ("categoryId[]", &filter.category_ids),
+        ("milestoneId[]", &filter.milestone_ids),
+    ];
+    for (name, values) in ids {
+        params.extend(values.iter().map(|id| format!("{name}={id}")));
+    }
+    let dates = [
+        ("createdSince", &filter.created_since),
+        ("createdUntil", &filter.created_until),
+        ("updatedSince", &filter.updated_since),
+        ("updatedUntil", &filter.updated_until),
+    ];
+    for (name, value) in dates {
+        if let Some(value) = value {
+            params.push(format!("{name}={}", urlencoding::encode(value)));
+        }
+    }
+    params.join("&")
+}
+
+fn to_listing(issue: BacklogIssue) -> IssueListing {
+    IssueListing {
+        facets: facets_of(&issue),
+        created_at: issue.created,
+        due_date: issue.due_date,
+        summary: IssueSummary {
+            issue_key: issue.issue_key,
+            summary: issue.summary,
+            updated_at: issue.updated,
+        },
+    }
+}
+
+fn facets_of(issue: &BacklogIssue) -> IssueFacets {
+    IssueFacets {
+        project_id: issue.project_id,
+        status_id: issue.status.as_ref().map(|s| s.id),
+        assignee_id: issue.assignee.as_ref().and_then(|u| u.id),
+        issue_type_id: issue.issue_type.as_ref().map(|t| t.id),
+        category_ids: issue.category.iter().map(|c| c.id).collect(),
+        milestone_ids: issue.milestone.iter().map(|m| m.id).collect(),
+    }
+}
+
+fn token_lock_error() -> AppError {
+    AppError::Unknown("OAuth token lock poisoned".to_string())
+}
+
+fn names(items: Vec<BacklogNamed>) -> Vec<String> {
+    items.into_iter().map(|item| item.name).collect()
+}
+
 fn map_status(response: Response) -> AppResult<Response> {
     match map_status_code(response.status()) {
         Ok(()) => Ok(response),
@@ -159,7 +666,7 @@ fn map_status(response: Response) -> AppResult<Response> {
     }
 }
 
-fn map_status_code(status: StatusCode) -> AppResult<()> {
+pub fn map_status_code(status: StatusCode) -> AppResult<()> {
     match status {
         StatusCode::OK => Ok(()),
         StatusCode::UNAUTHORIZED => Err(AppError::AuthInvalid),
@@ -173,8 +680,12 @@ fn map_status_code(status: StatusCode) -> AppResult<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::map_status_code;
+    use super::{facets_of, issue_query, map_status_code, BacklogApi, BacklogIssue, BacklogWiki};
+    use crate::app_error::AppError;
+    use crate::mock_backlog::{self, MockBacklog};
+    use crate::models::{IssueFilter, IssueSort, SortOrder};
     use reqwest::StatusCode;
+    use serde_json::json;
 
     #[test]
     fn status_mapping_works() {
@@ -193,4 +704,121 @@ mod tests {
         let err = map_status_code(StatusCode::FORBIDDEN).expect_err("should map to error");
         assert!(matches!(err, crate::app_error::AppError::Forbidden));
     }
+
+    #[test]
+    fn issue_metadata_tolerates_missing_fields() {
+        let issue: BacklogIssue = serde_json::from_str(
+            r#"{
+                "issueKey": "PROJ-2",
+                "summary": "子課題",
+                "description": null,
+                "updated": "2026-01-02T00:00:00Z",
+                "status": {"id": 2, "name": "処理中"},
+                "assignee": null,
+                "category": [{"id": 1, "name": "UI"}, {"id": 2, "name": "API"}],
+                "parentIssueId": 10
+            }"#,
+        )
+        .expect("deserialize");
+
+        assert_eq!(issue.status.map(|s| s.name).as_deref(), Some("処理中"));
+        assert!(issue.assignee.is_none());
+        assert_eq!(super::names(issue.category), vec!["UI", "API"]);
+        assert!(issue.milestone.is_empty());
+        assert_eq!(issue.parent_issue_id, Some(10));
+    }
+
+    #[test]
+    fn issue_filters_map_onto_query_parameters() {
+        assert_eq!(
+            issue_query(&IssueFilter::default()),
+            "sort=updated&order=desc"
+        );
+
+        let filter = IssueFilter {
+            keyword: Some(" ログイン 不具合 ".to_string()),
+            project_ids: vec![12],
+            status_ids: vec![1, 2],
+            assignee_ids: vec![7],
+            milestone_ids: vec![30],
+            created_since: Some("2026-01-01".to_string()),
+            updated_until: Some("2026-03-31".to_string()),
+            sort: IssueSort::DueDate,
+            order: SortOrder::Asc,
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            issue_query(&filter),
+            "sort=dueDate&order=asc&keyword=%E3%83%AD%E3%82%B0%E3%82%A4%E3%83%B3%20%E4%B8%8D%E5%85%B7%E5%90%88\
+             &projectId[]=12&statusId[]=1&statusId[]=2&assigneeId[]=7&milestoneId[]=30\
+             &createdSince=2026-01-01&updatedUntil=2026-03-31"
+        );
+    }
+
+    #[test]
+    fn issue_facets_keep_filterable_ids() {
+        let issue: BacklogIssue = serde_json::from_str(
+            r#"{
+                "projectId": 12,
+                "issueKey": "PROJ-3",
+                "summary": "facets",
+                "updated": "2026-01-02T00:00:00Z",
+                "status": {"id": 2, "name": "処理中"},
+                "assignee": {"id": 7, "name": "山田"},
+                "category": [{"id": 1, "name": "UI"}],
+                "milestone": [{"id": 30, "name": "v1"}, {"id": 31, "name": "v2"}]
+            }"#,
+        )
+        .expect("deserialize");
+
+        let facets = facets_of(&issue);
+        assert_eq!(facets.project_id, Some(12));
+        assert_eq!(facets.status_id, Some(2));
+        assert_eq!(facets.assignee_id, Some(7));
+        assert_eq!(facets.issue_type_id, None);
+        assert_eq!(facets.category_ids, vec![1]);
+        assert_eq!(facets.milestone_ids, vec![30, 31]);
+    }
+
+    #[test]
+    fn wiki_listing_has_no_content() {
+        let pages: Vec<BacklogWiki> = serde_json::from_str(
+            r#"[{
+                "id": 112,
+                "projectId": 12,
+                "name": "設計/API",
+                "tags": [{"id": 1, "name": "仕様"}],
+                "updated": "2026-01-02T00:00:00Z"
+            }]"#,
+        )
+        .expect("deserialize");
+
+        assert_eq!(pages[0].name, "設計/API");
+        assert!(pages[0].content.is_none());
+        assert_eq!(pages[0].tags[0].name, "仕様");
+    }
+
+    #[test]
+    fn client_talks_to_the_backlog_endpoints() {
+        let mock = MockBacklog::start();
+        mock.respond(
+            "/api/v2/projects",
+            json!([mock_backlog::project(1, "PROJ")]),
+        );
+        let client = mock.client();
+
+        client.verify_connection().expect("myself");
+        let projects = client.fetch_projects().expect("projects");
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].project_key, "PROJ");
+        assert_eq!(projects[0].text_formatting_rule, "markdown");
+        assert_eq!(
+            mock.requests(),
+            ["/api/v2/users/myself", "/api/v2/projects"]
+        );
+
+        mock.fail("/api/v2/users/myself", StatusCode::UNAUTHORIZED);
+        let err = client.verify_connection().expect_err("rejected key");
+        assert!(matches!(err, AppError::AuthInvalid), "{err:?}");
+    }
 }
@@ -1,29 +1,54 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
+use chrono::NaiveDate;
 use directories::ProjectDirs;
 use serde::Serialize;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 use crate::app_error::{AppError, AppResult};
-use crate::backlog::BacklogClient;
+use crate::backlog::{BacklogApi, BacklogClient, ISSUE_PAGE_SIZE};
 use crate::db::Db;
+use crate::export;
 use crate::keychain;
-use crate::models::{ExportHistory, IssueDetail, IssueSummary, Project, SetupState};
+use crate::markdown::{self, ConvertOptions, IssueLinkStyle};
+use crate::models::{
+    Attachment, AuthMethod, BulkExportItem, BulkExportResult, BulkExportTarget, Comment,
+    ConversionReport, ExportHistory, ExportTemplate, FilterOptions, FrontMatterSettings,
+    IssueDetail, IssueFilter, IssueSearchPage, IssueSummary, Profile, Project, RateLimitStatus,
+    SetupAuth, SetupState, WikiExportItem, WikiExportResult, WikiPage, WikiSummary,
+};
+use crate::oauth::{self, OAuthApp, OAuthCredentials, SharedCredentials};
+use crate::profiles::{self, ProfileStore};
+use crate::template::Template;
+use crate::throttle::Throttle;
 
 pub struct AppState {
-    pub db_path: PathBuf,
-    pub api_key_cache: Mutex<Option<String>>,
+    pub data_dir: PathBuf,
+    // The profile whose database, credentials and settings commands use.
+    pub profile_id: Mutex<i64>,
+    // Credentials by profile id, so a client still running for one profile
+    // never reads or writes another's.
+    pub api_key_cache: Mutex<HashMap<i64, String>>,
+    // Shared with clients, which refresh through it one at a time.
+    pub oauth_cache: Mutex<HashMap<i64, SharedCredentials>>,
+    // One API budget for the whole app, whichever command is using it.
+    pub throttle: Arc<Throttle>,
 }
 
 impl AppState {
     pub fn new() -> AppResult<Self> {
-        let db_path = database_path()?;
-        let _ = Db::open(&db_path)?;
+        let data_dir = data_dir()?;
+        let profile_id = ProfileStore::open(&data_dir.join(PROFILES_FILE))?.active_id()?;
+        let _ = Db::open(&data_dir.join(profiles::database_file(profile_id)))?;
         Ok(Self {
-            db_path,
-            api_key_cache: Mutex::new(None),
+            data_dir,
+            profile_id: Mutex::new(profile_id),
+            api_key_cache: Mutex::default(),
+            oauth_cache: Mutex::default(),
+            throttle: Arc::default(),
         })
     }
 }
@@ -32,30 +57,49 @@ impl AppState {
 #[serde(rename_all = "camelCase")]
 pub struct ExportResult {
     pub path: String,
+    pub attachment_count: usize,
 }
 
-fn database_path() -> AppResult<PathBuf> {
+const PROFILES_FILE: &str = "profiles.db";
+
+fn data_dir() -> AppResult<PathBuf> {
     let dirs = ProjectDirs::from("com", "company", "backlog-markdown-exporter")
         .ok_or_else(|| AppError::Unknown("cannot resolve data dir".to_string()))?;
-    Ok(dirs.data_dir().join("app.db"))
+    Ok(dirs.data_dir().to_path_buf())
+}
+
+fn active_profile_id(state: &State<AppState>) -> AppResult<i64> {
+    state
+        .profile_id
+        .lock()
+        .map(|id| *id)
+        .map_err(|_| AppError::Unknown("profile lock poisoned".to_string()))
 }
 
 fn open_db(state: &State<AppState>) -> AppResult<Db> {
-    Db::open(Path::new(&state.db_path))
+    open_profile_db(active_profile_id(state)?, state)
+}
+
+fn open_profile_db(profile_id: i64, state: &State<AppState>) -> AppResult<Db> {
+    Db::open(&state.data_dir.join(profiles::database_file(profile_id)))
 }
 
-fn resolve_api_key(state: &State<AppState>) -> AppResult<String> {
+fn open_profiles(state: &State<AppState>) -> AppResult<ProfileStore> {
+    ProfileStore::open(&state.data_dir.join(PROFILES_FILE))
+}
+
+fn resolve_api_key(profile_id: i64, state: &State<AppState>) -> AppResult<String> {
     if let Ok(cache) = state.api_key_cache.lock() {
-        if let Some(key) = cache.as_ref() {
+        if let Some(key) = cache.get(&profile_id) {
             return pick_api_key(Some(key.clone()), None);
         }
     }
 
-    let loaded = keychain::load_api_key()?;
+    let loaded = keychain::load_api_key(profile_id)?;
     let key = pick_api_key(None, loaded)?;
 
     if let Ok(mut cache) = state.api_key_cache.lock() {
-        *cache = Some(key.clone());
+        cache.insert(profile_id, key.clone());
     }
     Ok(key)
 }
@@ -78,48 +122,164 @@ fn pick_api_key(cached: Option<String>, loaded: Option<String>) -> AppResult<Str
     ))
 }
 
+fn resolve_oauth_credentials(
+    profile_id: i64,
+    space_url: &str,
+    state: &State<AppState>,
+) -> AppResult<SharedCredentials> {
+    if let Ok(cache) = state.oauth_cache.lock() {
+        if let Some(credentials) = cache.get(&profile_id) {
+            return Ok(Arc::clone(credentials));
+        }
+    }
+
+    let credentials =
+        keychain::load_oauth_credentials(profile_id, space_url)?.ok_or_else(|| {
+            AppError::Keychain("OAuth tokens are not configured in Keychain".to_string())
+        })?;
+    let credentials = Arc::new(Mutex::new(credentials));
+    if let Ok(mut cache) = state.oauth_cache.lock() {
+        cache.insert(profile_id, Arc::clone(&credentials));
+    }
+    Ok(credentials)
+}
+
 fn get_client(state: &State<AppState>) -> AppResult<BacklogClient> {
-    let db = open_db(state)?;
+    connect(state).map(|(client, _)| client)
+}
+
+// The client and database of the active profile, resolved together so that
+// a long command keeps working on one profile even if the user switches to
+// another while it runs.
+fn connect(state: &State<AppState>) -> AppResult<(BacklogClient, Db)> {
+    let profile_id = active_profile_id(state)?;
+    let db = open_profile_db(profile_id, state)?;
     let space_url = db
         .load_space_url()?
         .ok_or_else(|| AppError::Validation("Space URL is not configured".to_string()))?
         .trim()
         .to_string();
 
-    let api_key = resolve_api_key(state)?;
-
-    BacklogClient::new(&space_url, &api_key)
+    let client = match db.load_auth_method()? {
+        AuthMethod::ApiKey => BacklogClient::new(&space_url, &resolve_api_key(profile_id, state)?)?,
+        AuthMethod::OAuth => {
+            let credentials = resolve_oauth_credentials(profile_id, &space_url, state)?;
+            let space = space_url.clone();
+            BacklogClient::with_shared_oauth(&space_url, credentials, move |refreshed| {
+                keychain::save_oauth_credentials(profile_id, &space, refreshed)
+            })?
+        }
+    };
+    let client = client
+        .with_projects(db.list_projects()?)
+        .with_throttle(Arc::clone(&state.throttle));
+    Ok((client, db))
 }
 
-fn fetch_detail_online_first(issue_key: &str, state: &State<AppState>) -> AppResult<IssueDetail> {
-    let client = get_client(state)?;
-
+fn fetch_detail_online_first(
+    client: &dyn BacklogApi,
+    db: &Db,
+    issue_key: &str,
+) -> AppResult<IssueDetail> {
     match client.fetch_issue_by_key(issue_key) {
         Ok(detail) => {
-            let db = open_db(state)?;
             db.upsert_issue_detail(&detail)?;
             Ok(detail)
         }
         Err(e @ AppError::Network(_)) | Err(e @ AppError::RateLimit) => {
-            let db = open_db(state)?;
             db.get_issue_detail_local(issue_key)?.ok_or(e)
         }
         Err(e) => Err(e),
     }
 }
 
-#[tauri::command]
-pub fn setup_save(space_url: String, api_key: String, state: State<AppState>) -> Result<(), String> {
+fn fetch_comments_online_first(
+    client: &dyn BacklogApi,
+    db: &Db,
+    issue_key: &str,
+) -> AppResult<Vec<Comment>> {
+    match client.fetch_comments(issue_key) {
+        Ok(comments) => {
+            db.replace_comments(issue_key, &comments)?;
+            Ok(comments)
+        }
+        Err(AppError::Network(_)) | Err(AppError::RateLimit) => db.list_comments(issue_key),
+        Err(e) => Err(e),
+    }
+}
+
+// Runs off the main thread: with OAuth it waits for the user to finish
+// signing in to Backlog in the browser.
+#[tauri::command(async)]
+pub fn setup_save(
+    space_url: String,
+    auth: SetupAuth,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
     run(|| {
-        let client = BacklogClient::new(&space_url, &api_key)?;
-        client.verify_connection()?;
+        // Signing in can take a while; the result belongs to the profile it
+        // started for.
+        let profile_id = active_profile_id(&state)?;
+        let method = match auth {
+            SetupAuth::ApiKey { api_key } => {
+                let client = BacklogClient::new(&space_url, &api_key)?
+                    .with_throttle(Arc::clone(&state.throttle));
+                client.verify_connection()?;
 
-        keychain::save_api_key(&api_key)?;
-        if let Ok(mut cache) = state.api_key_cache.lock() {
-            *cache = Some(api_key.trim().to_string());
-        }
-        let db = open_db(&state)?;
+                keychain::save_api_key(profile_id, &api_key)?;
+                if let Ok(mut cache) = state.api_key_cache.lock() {
+                    cache.insert(profile_id, api_key.trim().to_string());
+                }
+                AuthMethod::ApiKey
+            }
+            SetupAuth::OAuth {
+                client_id,
+                client_secret,
+            } => {
+                let oauth_app = OAuthApp {
+                    client_id: client_id.trim().to_string(),
+                    client_secret: client_secret.trim().to_string(),
+                };
+                if oauth_app.client_id.is_empty() || oauth_app.client_secret.is_empty() {
+                    return Err(AppError::Validation(
+                        "client ID and client secret are required".to_string(),
+                    ));
+                }
+                let space = space_url.trim();
+                if space.is_empty() {
+                    return Err(AppError::Validation("space URL is required".to_string()));
+                }
+
+                let tokens = oauth::authorize(space, &oauth_app, oauth::REDIRECT_PORT, |url| {
+                    tauri::api::shell::open(&app.shell_scope(), url, None)
+                        .map_err(|e| AppError::Unknown(format!("cannot open the browser: {e}")))
+                })?;
+                let credentials = OAuthCredentials {
+                    app: oauth_app,
+                    tokens,
+                };
+                let client = BacklogClient::with_oauth(space, credentials.clone(), |_| Ok(()))?
+                    .with_throttle(Arc::clone(&state.throttle));
+                client.verify_connection()?;
+
+                keychain::save_oauth_credentials(profile_id, space, &credentials)?;
+                // Clients still running for the profile move to the new tokens.
+                if let Ok(mut cache) = state.oauth_cache.lock() {
+                    let shared = cache
+                        .entry(profile_id)
+                        .or_insert_with(|| Arc::new(Mutex::new(credentials.clone())));
+                    if let Ok(mut current) = shared.lock() {
+                        *current = credentials;
+                    }
+                }
+                AuthMethod::OAuth
+            }
+        };
+
+        let db = open_profile_db(profile_id, &state)?;
         db.save_space_url(&space_url)?;
+        db.save_auth_method(method)?;
         db.save_api_key_configured_marker(true)?;
         Ok(())
     })
@@ -131,34 +291,49 @@ pub fn setup_load(state: State<AppState>) -> Result<SetupState, String> {
         let db = open_db(&state)?;
         let space_url = db.load_space_url()?;
         let export_dir = db.load_export_dir()?;
+        let front_matter = db.load_front_matter()?;
+        let auth_method = db.load_auth_method()?;
         let configured_marker = db.load_api_key_configured_marker()?;
-        let has_api_key = match keychain::load_api_key() {
-            Ok(value) => value.is_some() || configured_marker,
+        let profile_id = active_profile_id(&state)?;
+        // `has_api_key` stands for either kind of credential.
+        let stored = match (auth_method, space_url.as_deref()) {
+            (AuthMethod::ApiKey, _) => keychain::load_api_key(profile_id).map(|v| v.is_some()),
+            (AuthMethod::OAuth, Some(space)) => {
+                keychain::load_oauth_credentials(profile_id, space).map(|v| v.is_some())
+            }
+            (AuthMethod::OAuth, None) => Ok(false),
+        };
+        let has_api_key = match stored {
+            Ok(value) => value || configured_marker,
             Err(_) => configured_marker,
         };
 
         Ok(SetupState {
             space_url,
             has_api_key,
+            auth_method,
+            oauth_redirect_uri: oauth::redirect_uri(oauth::REDIRECT_PORT),
             export_dir,
+            front_matter,
         })
     })
 }
 
-#[tauri::command]
+#[tauri::command(async)]
 pub fn projects_sync(state: State<AppState>) -> Result<Vec<Project>, String> {
     run(|| {
-        let client = get_client(&state)?;
+        let (client, db) = connect(&state)?;
         let projects = client.fetch_projects()?;
-
-        let db = open_db(&state)?;
         db.upsert_projects(&projects)?;
         db.list_projects()
     })
 }
 
-#[tauri::command]
-pub fn issues_search_by_key(issue_key: String, state: State<AppState>) -> Result<Vec<IssueSummary>, String> {
+#[tauri::command(async)]
+pub fn issues_search_by_key(
+    issue_key: String,
+    state: State<AppState>,
+) -> Result<Vec<IssueSummary>, String> {
     run(|| {
         let client = get_client(&state)?;
         let key = issue_key.trim();
@@ -188,92 +363,607 @@ pub fn issues_search_by_key(issue_key: String, state: State<AppState>) -> Result
     })
 }
 
-#[tauri::command]
-pub fn issues_search_by_keyword(keyword: String, state: State<AppState>) -> Result<Vec<IssueSummary>, String> {
+#[tauri::command(async)]
+pub fn issues_search_by_keyword(
+    keyword: String,
+    count: Option<usize>,
+    offset: Option<usize>,
+    state: State<AppState>,
+) -> Result<IssueSearchPage, String> {
     run(|| {
-        let query = keyword.trim();
-        if query.is_empty() {
+        if keyword.trim().is_empty() {
             return Err(AppError::Validation("keyword is required".to_string()));
         }
+        let filter = IssueFilter {
+            keyword: Some(keyword),
+            ..IssueFilter::default()
+        };
+        let (client, db) = connect(&state)?;
+        search_online_first(&client, &db, &filter, count, offset)
+    })
+}
 
-        let client = get_client(&state)?;
-        match client.search_issues_by_keyword(query) {
-            Ok(results) => {
-                let db = open_db(&state)?;
-                for item in &results {
-                    db.upsert_issue_summary(item)?;
-                }
-                Ok(results)
+#[tauri::command(async)]
+pub fn issues_search(
+    filter: IssueFilter,
+    count: Option<usize>,
+    offset: Option<usize>,
+    state: State<AppState>,
+) -> Result<IssueSearchPage, String> {
+    run(|| {
+        validate_filter(&filter)?;
+        let (client, db) = connect(&state)?;
+        search_online_first(&client, &db, &filter, count, offset)
+    })
+}
+
+// Counting the hits costs a request of its own, so only the first page does;
+// the UI keeps that total while it loads the rest.
+fn search_online_first(
+    client: &dyn BacklogApi,
+    db: &Db,
+    filter: &IssueFilter,
+    count: Option<usize>,
+    offset: Option<usize>,
+) -> AppResult<IssueSearchPage> {
+    let count = page_size(count)?;
+    let offset = offset.unwrap_or(0);
+
+    let results = client
+        .search_issues(filter, count, offset)
+        .and_then(|issues| {
+            let total = if offset == 0 {
+                Some(client.count_issues(filter)?)
+            } else {
+                None
+            };
+            Ok((issues, total))
+        });
+    match results {
+        Ok((listings, total)) => {
+            for listing in &listings {
+                db.upsert_issue_listing(listing)?;
             }
-            Err(e @ AppError::Network(_)) | Err(e @ AppError::RateLimit) => {
-                let db = open_db(&state)?;
-                let fallback = db.search_issue_summaries_local(query)?;
-                if fallback.is_empty() {
-                    Err(e)
-                } else {
-                    Ok(fallback)
-                }
+            Ok(IssueSearchPage {
+                issues: listings.into_iter().map(|l| l.summary).collect(),
+                total,
+                offset,
+            })
+        }
+        Err(e @ AppError::Network(_)) | Err(e @ AppError::RateLimit) => {
+            let (issues, total) = db.search_issues_local(filter, count, offset)?;
+            if total == 0 {
+                Err(e)
+            } else {
+                Ok(IssueSearchPage {
+                    issues,
+                    total: Some(total),
+                    offset,
+                })
             }
-            Err(e) => Err(e),
         }
-    })
+        Err(e) => Err(e),
+    }
 }
 
-#[tauri::command]
+fn validate_filter(filter: &IssueFilter) -> AppResult<()> {
+    let ranges = [
+        ("created", &filter.created_since, &filter.created_until),
+        ("updated", &filter.updated_since, &filter.updated_until),
+    ];
+    for (name, since, until) in ranges {
+        let since = parse_filter_date(&format!("{name}Since"), since)?;
+        let until = parse_filter_date(&format!("{name}Until"), until)?;
+        if let (Some(since), Some(until)) = (since, until) {
+            if since > until {
+                return Err(AppError::Validation(format!(
+                    "{name}Since must not be after {name}Until"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_filter_date(field: &str, value: &Option<String>) -> AppResult<Option<NaiveDate>> {
+    value
+        .as_deref()
+        .map(|v| {
+            NaiveDate::parse_from_str(v, "%Y-%m-%d").map_err(|_| {
+                AppError::Validation(format!("{field} must be a yyyy-MM-dd date, got {v:?}"))
+            })
+        })
+        .transpose()
+}
+
+// Statuses, users, issue types, categories and milestones of one project for
+// the search filters. These lists are only available online.
+#[tauri::command(async)]
+pub fn search_filter_options(
+    project_id: i64,
+    state: State<AppState>,
+) -> Result<FilterOptions, String> {
+    run(|| get_client(&state)?.fetch_filter_options(project_id))
+}
+
+// Backlog's own default is 20 issues per page and it allows at most 100.
+fn page_size(count: Option<usize>) -> AppResult<usize> {
+    match count {
+        None => Ok(20),
+        Some(count @ 1..=ISSUE_PAGE_SIZE) => Ok(count),
+        Some(count) => Err(AppError::Validation(format!(
+            "count must be between 1 and {ISSUE_PAGE_SIZE}, got {count}"
+        ))),
+    }
+}
+
+#[tauri::command(async)]
 pub fn issue_get_detail(issue_key: String, state: State<AppState>) -> Result<IssueDetail, String> {
     run(|| {
         let key = issue_key.trim();
-        fetch_detail_online_first(key, &state)
+        fetch_detail_online_first(&get_client(&state)?, &open_db(&state)?, key)
     })
 }
 
-#[tauri::command]
+// Lists the issues whose descriptions lose formatting when converted, so they
+// can be checked before exporting.
+#[tauri::command(async)]
+pub fn issues_conversion_report(
+    issue_keys: Vec<String>,
+    state: State<AppState>,
+) -> Result<Vec<ConversionReport>, String> {
+    run(|| {
+        let (client, db) = connect(&state)?;
+        let mut reports = Vec::new();
+        for key in &issue_keys {
+            let detail = fetch_detail_online_first(&client, &db, key.trim())?;
+            if !detail.conversion_warnings.is_empty() {
+                reports.push(ConversionReport {
+                    issue_key: detail.issue_key,
+                    summary: detail.summary,
+                    warnings: detail.conversion_warnings,
+                });
+            }
+        }
+        Ok(reports)
+    })
+}
+
+#[tauri::command(async)]
 pub fn issue_export_markdown(
     issue_key: String,
     target_dir: String,
     overwrite: bool,
+    link_issue_files: bool,
     state: State<AppState>,
 ) -> Result<ExportResult, String> {
     run(|| {
-        let key = issue_key.trim();
-        let detail = fetch_detail_online_first(key, &state)?;
+        let (client, db) = connect(&state)?;
+        let export = IssueExport::new(&client, &db, &target_dir, overwrite, link_issue_files)?;
+        export_issue(issue_key.trim(), &export)
+    })
+}
+
+// Exports every issue of the target one by one. A failing issue is recorded
+// in the history and the export moves on; only an invalid API key stops it.
+#[tauri::command(async)]
+pub fn issues_export_bulk(
+    target: BulkExportTarget,
+    target_dir: String,
+    overwrite: bool,
+    link_issue_files: bool,
+    state: State<AppState>,
+) -> Result<BulkExportResult, String> {
+    run(|| {
+        let (client, db) = connect(&state)?;
+        let issue_keys = resolve_bulk_target(target, &client, &db)?;
+        let export = IssueExport::new(&client, &db, &target_dir, overwrite, link_issue_files)?;
 
-        let target = PathBuf::from(target_dir);
-        if !target.exists() {
-            fs::create_dir_all(&target)?;
+        let mut items = Vec::with_capacity(issue_keys.len());
+        for key in issue_keys {
+            let item = match export_issue(&key, &export) {
+                Ok(result) => BulkExportItem {
+                    issue_key: key,
+                    path: Some(result.path),
+                    attachment_count: result.attachment_count,
+                    error: None,
+                },
+                Err(AppError::AuthInvalid) => return Err(AppError::AuthInvalid),
+                Err(e) => {
+                    db.insert_export_failure(&key, &e.to_string())?;
+                    BulkExportItem {
+                        issue_key: key,
+                        path: None,
+                        attachment_count: 0,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            items.push(item);
         }
 
-        let path = if overwrite {
-            target.join(format!("{key}.md"))
+        let failed = items.iter().filter(|item| item.error.is_some()).count();
+        Ok(BulkExportResult {
+            exported: items.len() - failed,
+            failed,
+            items,
+        })
+    })
+}
+
+fn resolve_bulk_target(
+    target: BulkExportTarget,
+    client: &dyn BacklogApi,
+    db: &Db,
+) -> AppResult<Vec<String>> {
+    let (project_key, keyword) = match target {
+        BulkExportTarget::Keys { issue_keys } => return Ok(unique_issue_keys(&issue_keys)),
+        BulkExportTarget::Project { project_key } => (Some(project_key), None),
+        BulkExportTarget::Search {
+            keyword,
+            project_key,
+        } => {
+            if keyword.trim().is_empty() {
+                return Err(AppError::Validation("keyword is required".to_string()));
+            }
+            (project_key, Some(keyword))
+        }
+    };
+
+    let project_ids = match project_key
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+    {
+        Some(key) => vec![project_by_key(db, key)?.id],
+        None => Vec::new(),
+    };
+    let filter = IssueFilter {
+        keyword,
+        project_ids,
+        ..IssueFilter::default()
+    };
+
+    let listings = client.list_all_issues(&filter)?;
+    for listing in &listings {
+        db.upsert_issue_listing(listing)?;
+    }
+    Ok(listings.into_iter().map(|l| l.summary.issue_key).collect())
+}
+
+fn project_by_key(db: &Db, project_key: &str) -> AppResult<Project> {
+    db.list_projects()?
+        .into_iter()
+        .find(|p| p.project_key == project_key)
+        .ok_or_else(|| AppError::Validation(format!("unknown project key: {project_key}")))
+}
+
+fn unique_issue_keys(issue_keys: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    for key in issue_keys
+        .iter()
+        .map(|k| k.trim())
+        .filter(|k| !k.is_empty())
+    {
+        if !keys.iter().any(|k| k == key) {
+            keys.push(key.to_string());
+        }
+    }
+    keys
+}
+
+// What the issues of one export share. Built once, so a bulk export does not
+// reconnect, reopen the database or reread the settings for every issue.
+struct IssueExport<'a> {
+    client: &'a dyn BacklogApi,
+    db: &'a Db,
+    space_url: String,
+    projects: Vec<Project>,
+    // Per project key, as projects differ in their text formatting rule.
+    options: HashMap<String, ConvertOptions>,
+    issue_links: IssueLinkStyle,
+    front_matter: FrontMatterSettings,
+    template: Template,
+    target: PathBuf,
+    overwrite: bool,
+}
+
+impl<'a> IssueExport<'a> {
+    fn new(
+        client: &'a dyn BacklogApi,
+        db: &'a Db,
+        target_dir: &str,
+        overwrite: bool,
+        link_issue_files: bool,
+    ) -> AppResult<Self> {
+        let space_url = db.load_space_url()?.unwrap_or_default();
+        let projects = db.list_projects()?;
+        // Issue mentions point at the sibling `<KEY>.md` files in the export
+        // directory instead of the Backlog web UI.
+        let issue_links = if link_issue_files {
+            IssueLinkStyle::File
         } else {
-            next_available_export_path(&target, key)
+            IssueLinkStyle::Web
         };
+        let options = projects
+            .iter()
+            .map(|p| {
+                let mut options =
+                    ConvertOptions::for_project(&space_url, Some(&p.project_key), &projects);
+                options.issue_links = issue_links;
+                (p.project_key.clone(), options)
+            })
+            .collect();
+        let template = export::parse_template(
+            db.load_export_template()?
+                .as_deref()
+                .unwrap_or(export::DEFAULT_TEMPLATE),
+        )?;
+        Ok(Self {
+            client,
+            front_matter: db.load_front_matter()?,
+            db,
+            space_url,
+            projects,
+            options,
+            issue_links,
+            template,
+            target: PathBuf::from(target_dir),
+            overwrite,
+        })
+    }
 
-        fs::write(&path, detail.description_md)?;
+    fn convert_options(&self, issue_key: &str) -> ConvertOptions {
+        let project_key = issue_key.rsplit_once('-').map_or(issue_key, |(p, _)| p);
+        self.options.get(project_key).cloned().unwrap_or_else(|| {
+            let mut options = ConvertOptions::for_issue(&self.space_url, issue_key, &self.projects);
+            options.issue_links = self.issue_links;
+            options
+        })
+    }
+}
 
-        let db = open_db(&state)?;
-        db.insert_export_history(key, &path.to_string_lossy())?;
+fn export_issue(key: &str, export: &IssueExport) -> AppResult<ExportResult> {
+    let (client, db, space_url) = (export.client, export.db, &export.space_url);
+    let detail = fetch_detail_online_first(client, db, key)?;
 
-        Ok(ExportResult {
-            path: path.to_string_lossy().to_string(),
+    // Attachments need the network; offline exports keep the image macros.
+    let attachments = match client.fetch_attachments(key) {
+        Ok(attachments) => attachments,
+        Err(AppError::Network(_)) | Err(AppError::RateLimit) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+
+    let mut options = export.convert_options(key);
+    let asset_dir = format!("assets/{key}");
+    if !attachments.is_empty() {
+        options.asset_dir = Some(asset_dir.clone());
+    }
+    let description = markdown::backlog_to_markdown(&detail.description_raw, &options);
+    let comments: Vec<Comment> = fetch_comments_online_first(client, db, key)?
+        .into_iter()
+        .map(|c| Comment {
+            content_md: markdown::backlog_to_markdown(&c.content_raw, &options),
+            ..c
         })
+        .collect();
+    let issue_url =
+        (!space_url.is_empty()).then(|| format!("{}/view/{key}", space_url.trim_end_matches('/')));
+    let exported_at = chrono::Utc::now().to_rfc3339();
+    let front_matter = export::front_matter(
+        &detail,
+        &export.front_matter,
+        issue_url.as_deref(),
+        &exported_at,
+    );
+    let content = export::render_issue(
+        &export.template,
+        &export::ExportInput {
+            detail: &detail,
+            description_md: &description,
+            comments: &comments,
+            attachments: &attachments,
+            asset_dir: &asset_dir,
+            front_matter: &front_matter,
+            issue_url: issue_url.as_deref(),
+            exported_at: &exported_at,
+        },
+    )?;
+
+    let target = &export.target;
+    if !target.exists() {
+        fs::create_dir_all(target)?;
+    }
+    save_attachments(client, key, &attachments, &target.join(&asset_dir))?;
+
+    let path = if export.overwrite {
+        target.join(format!("{key}.md"))
+    } else {
+        next_available_export_path(target, key)
+    };
+
+    fs::write(&path, content)?;
+
+    db.insert_export_history(key, &path.to_string_lossy())?;
+
+    Ok(ExportResult {
+        path: path.to_string_lossy().to_string(),
+        attachment_count: attachments.len(),
     })
 }
 
-fn next_available_export_path(target_dir: &Path, issue_key: &str) -> PathBuf {
-    let base = target_dir.join(format!("{issue_key}.md"));
+fn save_attachments(
+    client: &dyn BacklogApi,
+    issue_key: &str,
+    attachments: &[Attachment],
+    dir: &Path,
+) -> AppResult<()> {
+    if attachments.is_empty() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    let file_names = export::attachment_file_names(attachments);
+    for (attachment, file_name) in attachments.iter().zip(file_names) {
+        let bytes = client.download_attachment(issue_key, attachment.id)?;
+        fs::write(dir.join(file_name), bytes)?;
+    }
+    Ok(())
+}
+
+fn next_available_export_path(target_dir: &Path, file_stem: &str) -> PathBuf {
+    let base = target_dir.join(format!("{file_stem}.md"));
     if !base.exists() {
         return base;
     }
 
     for i in 1..=9_999 {
-        let candidate = target_dir.join(format!("{issue_key}({i}).md"));
+        let candidate = target_dir.join(format!("{file_stem}({i}).md"));
         if !candidate.exists() {
             return candidate;
         }
     }
 
-    target_dir.join(format!("{issue_key}(overflow).md"))
+    target_dir.join(format!("{file_stem}(overflow).md"))
+}
+
+#[tauri::command(async)]
+pub fn wikis_list(project_key: String, state: State<AppState>) -> Result<Vec<WikiSummary>, String> {
+    run(|| {
+        let (client, db) = connect(&state)?;
+        let project = project_by_key(&db, project_key.trim())?;
+        list_wikis_online_first(&client, &db, &project)
+    })
+}
+
+#[tauri::command(async)]
+pub fn wiki_get(wiki_id: i64, state: State<AppState>) -> Result<WikiPage, String> {
+    run(|| {
+        let (client, db) = connect(&state)?;
+        fetch_wiki_online_first(&client, &db, wiki_id)
+    })
+}
+
+// Writes the pages under `<target_dir>/wiki/<PROJECT_KEY>/`, one directory
+// per level of the page names. No ids means every page of the project. As
+// with bulk issue exports, only an invalid API key stops the run.
+#[tauri::command(async)]
+pub fn wikis_export(
+    project_key: String,
+    wiki_ids: Vec<i64>,
+    target_dir: String,
+    overwrite: bool,
+    state: State<AppState>,
+) -> Result<WikiExportResult, String> {
+    run(|| {
+        let (client, db) = connect(&state)?;
+        let project = project_by_key(&db, project_key.trim())?;
+        let pages: Vec<WikiSummary> = list_wikis_online_first(&client, &db, &project)?
+            .into_iter()
+            .filter(|p| wiki_ids.is_empty() || wiki_ids.contains(&p.id))
+            .collect();
+        let root = PathBuf::from(&target_dir)
+            .join("wiki")
+            .join(export::attachment_file_name(&project.project_key));
+
+        let mut items = Vec::with_capacity(pages.len());
+        for page in pages {
+            let item = match export_wiki(&client, &db, page.id, &root, overwrite) {
+                Ok(path) => WikiExportItem {
+                    wiki_id: page.id,
+                    name: page.name,
+                    path: Some(path.to_string_lossy().to_string()),
+                    error: None,
+                },
+                Err(AppError::AuthInvalid) => return Err(AppError::AuthInvalid),
+                Err(e) => WikiExportItem {
+                    wiki_id: page.id,
+                    name: page.name,
+                    path: None,
+                    error: Some(e.to_string()),
+                },
+            };
+            items.push(item);
+        }
+
+        let failed = items.iter().filter(|item| item.error.is_some()).count();
+        Ok(WikiExportResult {
+            exported: items.len() - failed,
+            failed,
+            items,
+        })
+    })
+}
+
+fn list_wikis_online_first(
+    client: &dyn BacklogApi,
+    db: &Db,
+    project: &Project,
+) -> AppResult<Vec<WikiSummary>> {
+    match client.fetch_wikis(&project.project_key) {
+        Ok(pages) => {
+            db.replace_wiki_list(project.id, &pages)?;
+            db.list_wikis_local(project.id)
+        }
+        Err(e @ AppError::Network(_)) | Err(e @ AppError::RateLimit) => {
+            let cached = db.list_wikis_local(project.id)?;
+            if cached.is_empty() {
+                Err(e)
+            } else {
+                Ok(cached)
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn fetch_wiki_online_first(client: &dyn BacklogApi, db: &Db, wiki_id: i64) -> AppResult<WikiPage> {
+    match client.fetch_wiki(wiki_id) {
+        Ok(page) => {
+            db.upsert_wiki_page(&page)?;
+            Ok(page)
+        }
+        Err(e @ AppError::Network(_)) | Err(e @ AppError::RateLimit) => {
+            db.get_wiki_local(wiki_id)?.ok_or(e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn export_wiki(
+    client: &dyn BacklogApi,
+    db: &Db,
+    wiki_id: i64,
+    root: &Path,
+    overwrite: bool,
+) -> AppResult<PathBuf> {
+    let page = fetch_wiki_online_first(client, db, wiki_id)?;
+    let path = root.join(export::wiki_file_path(&page.name));
+    let dir = path.parent().unwrap_or(root);
+    fs::create_dir_all(dir)?;
+
+    let path = if overwrite {
+        path
+    } else {
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        next_available_export_path(dir, &stem)
+    };
+    fs::write(&path, export::wiki_document(&page))?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub fn markdown_to_backlog(
+    markdown: String,
+    project_key: Option<String>,
+    state: State<AppState>,
+) -> Result<String, String> {
+    run(|| {
+        let db = open_db(&state)?;
+        let space_url = db.load_space_url()?.unwrap_or_default();
+        let options =
+            ConvertOptions::for_project(&space_url, project_key.as_deref(), &db.list_projects()?);
+        Ok(markdown::markdown_to_backlog(&markdown, &options))
+    })
 }
 
 #[tauri::command]
@@ -297,7 +987,9 @@ pub fn set_export_dir(export_dir: String, state: State<AppState>) -> Result<(),
     run(|| {
         let trimmed = export_dir.trim();
         if trimmed.is_empty() {
-            return Err(AppError::Validation("export directory is required".to_string()));
+            return Err(AppError::Validation(
+                "export directory is required".to_string(),
+            ));
         }
         let path = PathBuf::from(trimmed);
         if !path.exists() {
@@ -311,40 +1003,207 @@ pub fn set_export_dir(export_dir: String, state: State<AppState>) -> Result<(),
 }
 
 #[tauri::command]
-pub fn auth_reset(state: State<AppState>) -> Result<(), String> {
+pub fn set_front_matter(
+    settings: FrontMatterSettings,
+    state: State<AppState>,
+) -> Result<(), String> {
+    run(|| {
+        let db = open_db(&state)?;
+        db.save_front_matter(&settings)
+    })
+}
+
+#[tauri::command]
+pub fn export_template_get(state: State<AppState>) -> Result<ExportTemplate, String> {
     run(|| {
-        keychain::delete_api_key()?;
-        if let Ok(mut cache) = state.api_key_cache.lock() {
-            *cache = None;
+        let db = open_db(&state)?;
+        Ok(match db.load_export_template()? {
+            Some(template) => ExportTemplate {
+                template,
+                is_default: false,
+            },
+            None => ExportTemplate {
+                template: export::DEFAULT_TEMPLATE.to_string(),
+                is_default: true,
+            },
+        })
+    })
+}
+
+// `None` goes back to the bundled default template.
+#[tauri::command]
+pub fn set_export_template(template: Option<String>, state: State<AppState>) -> Result<(), String> {
+    run(|| {
+        let db = open_db(&state)?;
+        match template {
+            Some(template) => {
+                export::parse_template(&template)?;
+                db.save_export_template(&template)
+            }
+            None => db.clear_export_template(),
         }
+    })
+}
+
+// Polled by the UI while a command runs, to tell why it is slow. Like every
+// command that talks to Backlog it runs off the main thread: a paced request
+// can sleep for a whole rate-limit window.
+#[tauri::command(async)]
+pub fn rate_limit_status(state: State<AppState>) -> Result<RateLimitStatus, String> {
+    Ok(state.throttle.status())
+}
 
+#[tauri::command]
+pub fn auth_reset(state: State<AppState>) -> Result<(), String> {
+    run(|| {
         let db = open_db(&state)?;
+        let profile_id = active_profile_id(&state)?;
+        keychain::delete_api_key(profile_id)?;
+        if let Some(space_url) = db.load_space_url()? {
+            keychain::delete_oauth_credentials(profile_id, &space_url)?;
+        }
+        clear_credential_caches(profile_id, &state);
+
         db.clear_api_key_configured_marker()?;
+        db.clear_auth_method()?;
         db.clear_space_url()?;
         db.clear_export_dir()?;
         Ok(())
     })
 }
 
+fn clear_credential_caches(profile_id: i64, state: &State<AppState>) {
+    if let Ok(mut cache) = state.api_key_cache.lock() {
+        cache.remove(&profile_id);
+    }
+    if let Ok(mut cache) = state.oauth_cache.lock() {
+        cache.remove(&profile_id);
+    }
+}
+
+#[tauri::command]
+pub fn profiles_list(state: State<AppState>) -> Result<Vec<Profile>, String> {
+    run(|| open_profiles(&state)?.list())
+}
+
+#[tauri::command]
+pub fn profile_create(name: String, state: State<AppState>) -> Result<Profile, String> {
+    run(|| open_profiles(&state)?.create(&name))
+}
+
+#[tauri::command]
+pub fn profile_rename(profile_id: i64, name: String, state: State<AppState>) -> Result<(), String> {
+    run(|| open_profiles(&state)?.rename(profile_id, &name))
+}
+
+#[tauri::command]
+pub fn profile_switch(profile_id: i64, state: State<AppState>) -> Result<(), String> {
+    run(|| {
+        open_profiles(&state)?.switch(profile_id)?;
+        activate_profile(profile_id, &state)
+    })
+}
+
+// Removes the profile with its database and Keychain entries. Deleting the
+// active profile switches to the oldest remaining one.
+#[tauri::command]
+pub fn profile_delete(profile_id: i64, state: State<AppState>) -> Result<(), String> {
+    run(|| {
+        let profiles = open_profiles(&state)?;
+        let db_path = state.data_dir.join(profiles::database_file(profile_id));
+        let space_url = if db_path.exists() {
+            Db::open(&db_path)?.load_space_url()?
+        } else {
+            None
+        };
+        profiles.delete(profile_id)?;
+        clear_credential_caches(profile_id, &state);
+
+        // The profile is gone either way; leftovers only cost disk space.
+        let mut cleanup = vec![keychain::delete_api_key(profile_id)];
+        if let Some(space_url) = space_url {
+            cleanup.push(keychain::delete_oauth_credentials(profile_id, &space_url));
+        }
+        if db_path.exists() {
+            cleanup.push(fs::remove_file(&db_path).map_err(AppError::from));
+        }
+        for error in cleanup.into_iter().filter_map(Result::err) {
+            log::warn!("profile {profile_id} cleanup: {error}");
+        }
+
+        let active_id = profiles.active_id()?;
+        if active_id != active_profile_id(&state)? {
+            activate_profile(active_id, &state)?;
+        }
+        Ok(())
+    })
+}
+
+fn activate_profile(profile_id: i64, state: &State<AppState>) -> AppResult<()> {
+    let _ = Db::open(&state.data_dir.join(profiles::database_file(profile_id)))?;
+    let mut active = state
+        .profile_id
+        .lock()
+        .map_err(|_| AppError::Unknown("profile lock poisoned".to_string()))?;
+    *active = profile_id;
+    drop(active);
+
+    state.throttle.reset();
+    Ok(())
+}
+
 fn run<T>(f: impl FnOnce() -> AppResult<T>) -> Result<T, String> {
-    f().map_err(|e| e.to_json())
+    f().map_err(|e| {
+        let json = e.to_json();
+        log::warn!("command failed: {json}");
+        json
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::pick_api_key;
+    use std::fs;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use super::{
+        export_issue, fetch_comments_online_first, fetch_detail_online_first, page_size,
+        pick_api_key, search_online_first, unique_issue_keys, validate_filter, IssueExport,
+    };
+    use crate::app_error::AppError;
+    use crate::db::Db;
+    use crate::mock_backlog::{self, MockBacklog};
+    use crate::models::{BulkExportTarget, IssueFilter, Project};
+
+    const ISSUE_PATH: &str = "/api/v2/issues/PROJ-1";
+    const COMMENTS_PATH: &str = "/api/v2/issues/PROJ-1/comments";
+
+    fn mock_with_issue() -> MockBacklog {
+        let mock = MockBacklog::start();
+        let issue = mock_backlog::issue("PROJ-1", "ログイン不具合", "**再現手順**");
+        mock.respond(ISSUE_PATH, issue);
+        mock.respond(
+            COMMENTS_PATH,
+            json!([mock_backlog::comment(7, "確認しました")]),
+        );
+        mock
+    }
 
     #[test]
     fn pick_api_key_prefers_cache() {
-        let key = pick_api_key(Some("cached-key".to_string()), Some("loaded-key".to_string()))
-            .expect("should select cached key");
+        let key = pick_api_key(
+            Some("cached-key".to_string()),
+            Some("loaded-key".to_string()),
+        )
+        .expect("should select cached key");
         assert_eq!(key, "cached-key");
     }
 
     #[test]
     fn pick_api_key_uses_loaded_when_cache_missing() {
-        let key = pick_api_key(None, Some("loaded-key".to_string()))
-            .expect("should select loaded key");
+        let key =
+            pick_api_key(None, Some("loaded-key".to_string())).expect("should select loaded key");
         assert_eq!(key, "loaded-key");
     }
 
@@ -354,4 +1213,185 @@ mod tests {
             .expect_err("empty values should fail");
         assert!(err.to_string().contains("API key is not configured"));
     }
+
+    #[test]
+    fn bulk_keys_are_trimmed_and_deduplicated() {
+        let keys = ["PROJ-2", " PROJ-1 ", "", "PROJ-2"].map(String::from);
+        assert_eq!(unique_issue_keys(&keys), vec!["PROJ-2", "PROJ-1"]);
+    }
+
+    #[test]
+    fn bulk_targets_deserialize_from_the_frontend_shape() {
+        let target: BulkExportTarget = serde_json::from_str(
+            r#"{"kind": "search", "keyword": "ログイン", "projectKey": "PROJ"}"#,
+        )
+        .expect("search target");
+        assert!(matches!(
+            target,
+            BulkExportTarget::Search { keyword, project_key: Some(key) } if keyword == "ログイン" && key == "PROJ"
+        ));
+
+        let target: BulkExportTarget =
+            serde_json::from_str(r#"{"kind": "keys", "issueKeys": ["PROJ-1"]}"#)
+                .expect("keys target");
+        assert!(
+            matches!(target, BulkExportTarget::Keys { issue_keys } if issue_keys == ["PROJ-1"])
+        );
+    }
+
+    #[test]
+    fn search_page_size_follows_backlog_limits() {
+        assert_eq!(page_size(None).expect("default"), 20);
+        assert_eq!(page_size(Some(100)).expect("max"), 100);
+        assert!(page_size(Some(0)).is_err());
+        assert!(page_size(Some(101)).is_err());
+    }
+
+    #[test]
+    fn filter_dates_must_be_ordered_calendar_dates() {
+        let filter = |since: &str, until: &str| IssueFilter {
+            created_since: Some(since.to_string()),
+            created_until: Some(until.to_string()),
+            ..IssueFilter::default()
+        };
+
+        assert!(validate_filter(&filter("2026-01-01", "2026-01-31")).is_ok());
+        assert_eq!(
+            validate_filter(&filter("2026/01/01", "2026-01-31"))
+                .expect_err("bad format")
+                .to_string(),
+            "validation error: createdSince must be a yyyy-MM-dd date, got \"2026/01/01\""
+        );
+        assert!(validate_filter(&filter("2026-02-01", "2026-01-31")).is_err());
+    }
+
+    #[test]
+    fn detail_is_fetched_online_and_cached() {
+        let mock = mock_with_issue();
+        let db = Db::open_in_memory().expect("db");
+
+        let detail = fetch_detail_online_first(&mock.client(), &db, "PROJ-1").expect("detail");
+        assert_eq!(detail.summary, "ログイン不具合");
+        assert_eq!(detail.status.as_deref(), Some("未対応"));
+        assert_eq!(mock.requests(), [ISSUE_PATH]);
+
+        let cached = db
+            .get_issue_detail_local("PROJ-1")
+            .expect("read")
+            .expect("cached");
+        assert_eq!(cached.description_raw, "**再現手順**");
+    }
+
+    #[test]
+    fn detail_and_comments_fall_back_to_the_cache_when_backlog_is_unavailable() {
+        let mock = mock_with_issue();
+        let db = Db::open_in_memory().expect("db");
+        let client = mock.client();
+        fetch_detail_online_first(&client, &db, "PROJ-1").expect("online detail");
+        fetch_comments_online_first(&client, &db, "PROJ-1").expect("online comments");
+
+        mock.fail(ISSUE_PATH, StatusCode::SERVICE_UNAVAILABLE);
+        mock.fail(COMMENTS_PATH, StatusCode::SERVICE_UNAVAILABLE);
+        let detail = fetch_detail_online_first(&client, &db, "PROJ-1").expect("cached detail");
+        assert_eq!(detail.summary, "ログイン不具合");
+        let comments =
+            fetch_comments_online_first(&client, &db, "PROJ-1").expect("cached comments");
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].content_raw, "確認しました");
+    }
+
+    #[test]
+    fn bulk_exports_share_one_client_and_database() {
+        let mock = mock_with_issue();
+        let issue = mock_backlog::issue("PROJ-2", "画面崩れ", "PROJ-1 の続き");
+        mock.respond("/api/v2/issues/PROJ-2", issue);
+        mock.respond("/api/v2/issues/PROJ-2/comments", json!([]));
+        for key in ["PROJ-1", "PROJ-2"] {
+            mock.respond(&format!("/api/v2/issues/{key}/attachments"), json!([]));
+        }
+        let db = Db::open_in_memory().expect("db");
+        let project = Project {
+            id: 1,
+            project_key: "PROJ".to_string(),
+            name: "PROJ プロジェクト".to_string(),
+            text_formatting_rule: "backlog".to_string(),
+            synced_at: "2026-01-01T00:00:00Z".to_string(),
+        };
+        db.upsert_projects(&[project]).expect("projects");
+        let dir = std::env::temp_dir().join(format!("bulk-export-{}", std::process::id()));
+        let client = mock.client();
+        let export =
+            IssueExport::new(&client, &db, &dir.to_string_lossy(), true, true).expect("export");
+
+        for key in ["PROJ-1", "PROJ-2"] {
+            export_issue(key, &export).expect("exported");
+        }
+        let second = fs::read_to_string(dir.join("PROJ-2.md")).expect("file");
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(second.contains("[PROJ-1](PROJ-1.md)"), "{second}");
+        assert_eq!(db.list_exports(10).expect("history").len(), 2);
+        assert!(
+            mock.requests()
+                .iter()
+                .all(|path| path.starts_with("/api/v2/issues/")),
+            "{:?}",
+            mock.requests()
+        );
+    }
+
+    #[test]
+    fn only_the_first_search_page_counts_the_hits() {
+        let mock = MockBacklog::start();
+        let issues = json!([
+            mock_backlog::issue("PROJ-1", "ログイン不具合", ""),
+            mock_backlog::issue("PROJ-2", "画面崩れ", ""),
+        ]);
+        mock.respond("/api/v2/issues", issues);
+        mock.respond("/api/v2/issues/count", json!({"count": 4}));
+        let db = Db::open_in_memory().expect("db");
+        let client = mock.client();
+        let filter = IssueFilter {
+            keyword: Some("不具合".to_string()),
+            ..IssueFilter::default()
+        };
+
+        let first = search_online_first(&client, &db, &filter, Some(2), None).expect("first");
+        let second = search_online_first(&client, &db, &filter, Some(2), Some(2)).expect("second");
+
+        assert_eq!((first.total, first.issues.len()), (Some(4), 2));
+        assert_eq!((second.total, second.offset), (None, 2));
+        let counts = mock
+            .requests()
+            .iter()
+            .filter(|path| *path == "/api/v2/issues/count")
+            .count();
+        assert_eq!(counts, 1);
+    }
+
+    #[test]
+    fn unavailable_backlog_without_a_cache_is_an_error() {
+        let mock = MockBacklog::start();
+        mock.fail(ISSUE_PATH, StatusCode::SERVICE_UNAVAILABLE);
+        let db = Db::open_in_memory().expect("db");
+
+        let err = fetch_detail_online_first(&mock.client(), &db, "PROJ-1").expect_err("no cache");
+        assert!(matches!(err, AppError::Network(_)), "{err:?}");
+    }
+
+    #[test]
+    fn auth_and_missing_issue_errors_are_not_hidden_by_the_cache() {
+        let mock = mock_with_issue();
+        let db = Db::open_in_memory().expect("db");
+        let client = mock.client();
+        fetch_detail_online_first(&client, &db, "PROJ-1").expect("online detail");
+
+        mock.fail(ISSUE_PATH, StatusCode::UNAUTHORIZED);
+        let err = fetch_detail_online_first(&client, &db, "PROJ-1").expect_err("401");
+        assert!(matches!(err, AppError::AuthInvalid), "{err:?}");
+
+        mock.fail(ISSUE_PATH, StatusCode::NOT_FOUND);
+        let err = fetch_detail_online_first(&client, &db, "PROJ-1").expect_err("404");
+        assert!(matches!(err, AppError::NotFound), "{err:?}");
+    }
 }
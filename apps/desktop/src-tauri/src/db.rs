@@ -2,10 +2,14 @@ use std::fs;
 use std::path::Path;
 
 use chrono::Utc;
-use rusqlite::{params, Connection, OptionalExtension};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
 
 use crate::app_error::{AppError, AppResult};
-use crate::models::{ExportHistory, IssueDetail, IssueSummary, Project};
+use crate::models::{
+    AuthMethod, Comment, ExportHistory, FrontMatterSettings, IssueDetail, IssueFacets, IssueFilter,
+    IssueListing, IssueSort, IssueSummary, Project, SortOrder, WikiPage, WikiSummary,
+};
 
 pub struct Db {
     conn: Connection,
@@ -22,6 +26,15 @@ impl Db {
         Ok(db)
     }
 
+    #[cfg(test)]
+    pub fn open_in_memory() -> AppResult<Self> {
+        let db = Self {
+            conn: Connection::open_in_memory()?,
+        };
+        db.init_schema()?;
+        Ok(db)
+    }
+
     fn init_schema(&self) -> AppResult<()> {
         self.conn.execute_batch(
             "
@@ -34,6 +47,7 @@ impl Db {
                 id INTEGER PRIMARY KEY,
                 project_key TEXT NOT NULL,
                 name TEXT NOT NULL,
+                text_formatting_rule TEXT NOT NULL DEFAULT 'backlog',
                 synced_at TEXT NOT NULL
             );
 
@@ -43,17 +57,110 @@ impl Db {
                 description_raw TEXT,
                 description_md TEXT,
                 updated_at TEXT NOT NULL,
-                synced_at TEXT NOT NULL
+                synced_at TEXT NOT NULL,
+                conversion_warnings TEXT NOT NULL DEFAULT '[]',
+                issue_type TEXT,
+                status TEXT,
+                priority TEXT,
+                assignee TEXT,
+                created_user TEXT,
+                created_at TEXT,
+                due_date TEXT,
+                parent_issue_key TEXT,
+                categories TEXT NOT NULL DEFAULT '[]',
+                versions TEXT NOT NULL DEFAULT '[]',
+                milestones TEXT NOT NULL DEFAULT '[]',
+                project_id INTEGER,
+                status_id INTEGER,
+                assignee_id INTEGER,
+                issue_type_id INTEGER,
+                category_ids TEXT NOT NULL DEFAULT '[]',
+                milestone_ids TEXT NOT NULL DEFAULT '[]'
+            );
+
+            CREATE TABLE IF NOT EXISTS comments (
+                id INTEGER PRIMARY KEY,
+                issue_key TEXT NOT NULL,
+                author TEXT NOT NULL,
+                content_raw TEXT NOT NULL,
+                content_md TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
             );
 
+            CREATE INDEX IF NOT EXISTS idx_comments_issue_key ON comments(issue_key);
+
+            CREATE TABLE IF NOT EXISTS wikis (
+                id INTEGER PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                content_raw TEXT,
+                content_md TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                synced_at TEXT,
+                conversion_warnings TEXT NOT NULL DEFAULT '[]'
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_wikis_project_id ON wikis(project_id);
+
             CREATE TABLE IF NOT EXISTS exports (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 issue_key TEXT NOT NULL,
                 export_path TEXT NOT NULL,
-                exported_at TEXT NOT NULL
+                exported_at TEXT NOT NULL,
+                error TEXT
             );
             ",
         )?;
+
+        self.ensure_column(
+            "projects",
+            "text_formatting_rule",
+            "TEXT NOT NULL DEFAULT 'backlog'",
+        )?;
+        for (column, definition) in [
+            ("conversion_warnings", "TEXT NOT NULL DEFAULT '[]'"),
+            ("issue_type", "TEXT"),
+            ("status", "TEXT"),
+            ("priority", "TEXT"),
+            ("assignee", "TEXT"),
+            ("created_user", "TEXT"),
+            ("created_at", "TEXT"),
+            ("due_date", "TEXT"),
+            ("parent_issue_key", "TEXT"),
+            ("categories", "TEXT NOT NULL DEFAULT '[]'"),
+            ("versions", "TEXT NOT NULL DEFAULT '[]'"),
+            ("milestones", "TEXT NOT NULL DEFAULT '[]'"),
+            ("project_id", "INTEGER"),
+            ("status_id", "INTEGER"),
+            ("assignee_id", "INTEGER"),
+            ("issue_type_id", "INTEGER"),
+            ("category_ids", "TEXT NOT NULL DEFAULT '[]'"),
+            ("milestone_ids", "TEXT NOT NULL DEFAULT '[]'"),
+        ] {
+            self.ensure_column("issues", column, definition)?;
+        }
+        self.ensure_column("exports", "error", "TEXT")?;
+        Ok(())
+    }
+
+    // `CREATE TABLE IF NOT EXISTS` leaves tables from older versions as they
+    // were, so columns added later are introduced here.
+    fn ensure_column(&self, table: &str, column: &str, definition: &str) -> AppResult<()> {
+        let mut stmt = self.conn.prepare(&format!("PRAGMA table_info({table})"))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .any(|name| name == column);
+
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"),
+                [],
+            )?;
+        }
         Ok(())
     }
 
@@ -115,6 +222,37 @@ impl Db {
         Ok(())
     }
 
+    pub fn save_auth_method(&self, method: AuthMethod) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO app_settings(key, value) VALUES('auth_method', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![method.key()],
+        )?;
+        Ok(())
+    }
+
+    // Spaces set up before OAuth was supported use an API key.
+    pub fn load_auth_method(&self) -> AppResult<AuthMethod> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM app_settings WHERE key = 'auth_method'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(match value.as_deref() {
+            Some("oauth") => AuthMethod::OAuth,
+            _ => AuthMethod::ApiKey,
+        })
+    }
+
+    pub fn clear_auth_method(&self) -> AppResult<()> {
+        self.conn
+            .execute("DELETE FROM app_settings WHERE key = 'auth_method'", [])?;
+        Ok(())
+    }
+
     pub fn save_export_dir(&self, export_dir: &str) -> AppResult<()> {
         self.conn.execute(
             "INSERT INTO app_settings(key, value) VALUES('export_dir', ?1)
@@ -142,33 +280,90 @@ impl Db {
         Ok(())
     }
 
+    pub fn save_front_matter(&self, settings: &FrontMatterSettings) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO app_settings(key, value) VALUES('front_matter', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![to_json(settings)?],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_front_matter(&self) -> AppResult<FrontMatterSettings> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM app_settings WHERE key = 'front_matter'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(value.map(|v| from_json(&v)).unwrap_or_default())
+    }
+
+    pub fn save_export_template(&self, template: &str) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO app_settings(key, value) VALUES('export_template', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![template],
+        )?;
+        Ok(())
+    }
+
+    pub fn load_export_template(&self) -> AppResult<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM app_settings WHERE key = 'export_template'",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    pub fn clear_export_template(&self) -> AppResult<()> {
+        self.conn
+            .execute("DELETE FROM app_settings WHERE key = 'export_template'", [])?;
+        Ok(())
+    }
+
     pub fn upsert_projects(&self, projects: &[Project]) -> AppResult<()> {
         let now = Utc::now().to_rfc3339();
         let mut stmt = self.conn.prepare(
-            "INSERT INTO projects(id, project_key, name, synced_at)
-             VALUES(?1, ?2, ?3, ?4)
+            "INSERT INTO projects(id, project_key, name, text_formatting_rule, synced_at)
+             VALUES(?1, ?2, ?3, ?4, ?5)
              ON CONFLICT(id) DO UPDATE SET
                  project_key = excluded.project_key,
                  name = excluded.name,
+                 text_formatting_rule = excluded.text_formatting_rule,
                  synced_at = excluded.synced_at",
         )?;
 
         for p in projects {
-            stmt.execute(params![p.id, p.project_key, p.name, now])?;
+            stmt.execute(params![
+                p.id,
+                p.project_key,
+                p.name,
+                p.text_formatting_rule,
+                now
+            ])?;
         }
         Ok(())
     }
 
     pub fn list_projects(&self) -> AppResult<Vec<Project>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, project_key, name, synced_at FROM projects ORDER BY project_key ASC",
+            "SELECT id, project_key, name, text_formatting_rule, synced_at
+             FROM projects ORDER BY project_key ASC",
         )?;
         let rows = stmt.query_map([], |row| {
             Ok(Project {
                 id: row.get(0)?,
                 project_key: row.get(1)?,
                 name: row.get(2)?,
-                synced_at: row.get(3)?,
+                text_formatting_rule: row.get(3)?,
+                synced_at: row.get(4)?,
             })
         })?;
 
@@ -182,40 +377,206 @@ impl Db {
     pub fn upsert_issue_detail(&self, detail: &IssueDetail) -> AppResult<()> {
         let now = Utc::now().to_rfc3339();
         self.conn.execute(
-            "INSERT INTO issues(issue_key, summary, description_raw, description_md, updated_at, synced_at)
-             VALUES(?1, ?2, ?3, ?4, ?5, ?6)
+            "INSERT INTO issues(issue_key, summary, description_raw, description_md, updated_at, synced_at, conversion_warnings,
+                                issue_type, status, priority, assignee, created_user, created_at, due_date, parent_issue_key,
+                                categories, versions, milestones,
+                                project_id, status_id, assignee_id, issue_type_id, category_ids, milestone_ids)
+             VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18,
+                    ?19, ?20, ?21, ?22, ?23, ?24)
              ON CONFLICT(issue_key) DO UPDATE SET
                 summary = excluded.summary,
                 description_raw = excluded.description_raw,
                 description_md = excluded.description_md,
                 updated_at = excluded.updated_at,
-                synced_at = excluded.synced_at",
+                synced_at = excluded.synced_at,
+                conversion_warnings = excluded.conversion_warnings,
+                issue_type = excluded.issue_type,
+                status = excluded.status,
+                priority = excluded.priority,
+                assignee = excluded.assignee,
+                created_user = excluded.created_user,
+                created_at = excluded.created_at,
+                due_date = excluded.due_date,
+                parent_issue_key = excluded.parent_issue_key,
+                categories = excluded.categories,
+                versions = excluded.versions,
+                milestones = excluded.milestones,
+                project_id = excluded.project_id,
+                status_id = excluded.status_id,
+                assignee_id = excluded.assignee_id,
+                issue_type_id = excluded.issue_type_id,
+                category_ids = excluded.category_ids,
+                milestone_ids = excluded.milestone_ids",
             params![
                 detail.issue_key,
                 detail.summary,
                 detail.description_raw,
                 detail.description_md,
                 detail.updated_at,
-                now
+                now,
+                to_json(&detail.conversion_warnings)?,
+                detail.issue_type,
+                detail.status,
+                detail.priority,
+                detail.assignee,
+                detail.created_user,
+                detail.created_at,
+                detail.due_date,
+                detail.parent_issue_key,
+                to_json(&detail.categories)?,
+                to_json(&detail.versions)?,
+                to_json(&detail.milestones)?,
+                detail.facets.project_id,
+                detail.facets.status_id,
+                detail.facets.assignee_id,
+                detail.facets.issue_type_id,
+                to_json(&detail.facets.category_ids)?,
+                to_json(&detail.facets.milestone_ids)?
             ],
         )?;
         Ok(())
     }
 
-    pub fn upsert_issue_summary(&self, summary: &IssueSummary) -> AppResult<()> {
+    // Search hits update what they carry and leave a cached description alone.
+    pub fn upsert_issue_listing(&self, listing: &IssueListing) -> AppResult<()> {
         let now = Utc::now().to_rfc3339();
+        let summary = &listing.summary;
+        let facets = &listing.facets;
         self.conn.execute(
-            "INSERT INTO issues(issue_key, summary, updated_at, synced_at)
-             VALUES(?1, ?2, ?3, ?4)
+            "INSERT INTO issues(issue_key, summary, updated_at, synced_at, created_at, due_date,
+                                project_id, status_id, assignee_id, issue_type_id, category_ids, milestone_ids)
+             VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
              ON CONFLICT(issue_key) DO UPDATE SET
                 summary = excluded.summary,
                 updated_at = excluded.updated_at,
-                synced_at = excluded.synced_at",
-            params![summary.issue_key, summary.summary, summary.updated_at, now],
+                synced_at = excluded.synced_at,
+                created_at = excluded.created_at,
+                due_date = excluded.due_date,
+                project_id = excluded.project_id,
+                status_id = excluded.status_id,
+                assignee_id = excluded.assignee_id,
+                issue_type_id = excluded.issue_type_id,
+                category_ids = excluded.category_ids,
+                milestone_ids = excluded.milestone_ids",
+            params![
+                summary.issue_key,
+                summary.summary,
+                summary.updated_at,
+                now,
+                listing.created_at,
+                listing.due_date,
+                facets.project_id,
+                facets.status_id,
+                facets.assignee_id,
+                facets.issue_type_id,
+                to_json(&facets.category_ids)?,
+                to_json(&facets.milestone_ids)?
+            ],
         )?;
         Ok(())
     }
 
+    // The offline counterpart of the API search: the same filters over the
+    // cached issues. Returns one page and the number of matches.
+    pub fn search_issues_local(
+        &self,
+        filter: &IssueFilter,
+        count: usize,
+        offset: usize,
+    ) -> AppResult<(Vec<IssueSummary>, u64)> {
+        let mut clauses: Vec<String> = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(keyword) = filter.keyword() {
+            clauses.push("(issue_key LIKE ? OR summary LIKE ?)".to_string());
+            let like = format!("%{keyword}%");
+            values.push(Value::Text(like.clone()));
+            values.push(Value::Text(like));
+        }
+        let ids = [
+            ("project_id", &filter.project_ids),
+            ("status_id", &filter.status_ids),
+            ("assignee_id", &filter.assignee_ids),
+            ("issue_type_id", &filter.issue_type_ids),
+        ];
+        for (column, ids) in ids {
+            if !ids.is_empty() {
+                clauses.push(format!("{column} IN ({})", placeholders(ids.len())));
+                values.extend(ids.iter().map(|id| Value::Integer(*id)));
+            }
+        }
+        let id_lists = [
+            ("category_ids", &filter.category_ids),
+            ("milestone_ids", &filter.milestone_ids),
+        ];
+        for (column, ids) in id_lists {
+            if !ids.is_empty() {
+                clauses.push(format!(
+                    "EXISTS (SELECT 1 FROM json_each({column}) WHERE value IN ({}))",
+                    placeholders(ids.len())
+                ));
+                values.extend(ids.iter().map(|id| Value::Integer(*id)));
+            }
+        }
+        // Timestamps are ISO 8601, so their first ten characters are the date.
+        // That is the UTC date, while Backlog compares in the space's time
+        // zone; the UI points this out next to the date fields.
+        let dates = [
+            ("created_at", ">=", &filter.created_since),
+            ("created_at", "<=", &filter.created_until),
+            ("updated_at", ">=", &filter.updated_since),
+            ("updated_at", "<=", &filter.updated_until),
+        ];
+        for (column, op, date) in dates {
+            if let Some(date) = date {
+                clauses.push(format!("substr({column}, 1, 10) {op} ?"));
+                values.push(Value::Text(date.clone()));
+            }
+        }
+
+        let where_clause = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let total: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM issues {where_clause}"),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        let sort_column = match filter.sort {
+            IssueSort::Created => "created_at",
+            IssueSort::Updated => "updated_at",
+            IssueSort::DueDate => "due_date",
+        };
+        let order = match filter.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        values.push(Value::Integer(count as i64));
+        values.push(Value::Integer(offset as i64));
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT issue_key, summary, updated_at
+             FROM issues {where_clause}
+             ORDER BY {sort_column} IS NULL, {sort_column} {order}, issue_key
+             LIMIT ? OFFSET ?"
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok(IssueSummary {
+                issue_key: row.get(0)?,
+                summary: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok((out, total as u64))
+    }
+
     pub fn search_issue_summaries_local(&self, keyword: &str) -> AppResult<Vec<IssueSummary>> {
         let like = format!("%{}%", keyword);
         let mut stmt = self.conn.prepare(
@@ -243,7 +604,10 @@ impl Db {
         let detail = self
             .conn
             .query_row(
-                "SELECT issue_key, summary, COALESCE(description_raw, ''), COALESCE(description_md, ''), updated_at, synced_at
+                "SELECT issue_key, summary, COALESCE(description_raw, ''), COALESCE(description_md, ''), updated_at, synced_at, conversion_warnings,
+                        issue_type, status, priority, assignee, created_user, created_at, due_date, parent_issue_key,
+                        categories, versions, milestones,
+                        project_id, status_id, assignee_id, issue_type_id, category_ids, milestone_ids
                  FROM issues WHERE issue_key = ?1",
                 params![issue_key],
                 |row| {
@@ -254,6 +618,26 @@ impl Db {
                         description_md: row.get(3)?,
                         updated_at: row.get(4)?,
                         synced_at: row.get(5)?,
+                        conversion_warnings: from_json(&row.get::<_, String>(6)?),
+                        issue_type: row.get(7)?,
+                        status: row.get(8)?,
+                        priority: row.get(9)?,
+                        assignee: row.get(10)?,
+                        created_user: row.get(11)?,
+                        created_at: row.get(12)?,
+                        due_date: row.get(13)?,
+                        parent_issue_key: row.get(14)?,
+                        categories: from_json(&row.get::<_, String>(15)?),
+                        versions: from_json(&row.get::<_, String>(16)?),
+                        milestones: from_json(&row.get::<_, String>(17)?),
+                        facets: IssueFacets {
+                            project_id: row.get(18)?,
+                            status_id: row.get(19)?,
+                            assignee_id: row.get(20)?,
+                            issue_type_id: row.get(21)?,
+                            category_ids: from_json(&row.get::<_, String>(22)?),
+                            milestone_ids: from_json(&row.get::<_, String>(23)?),
+                        },
                     })
                 },
             )
@@ -261,6 +645,170 @@ impl Db {
         Ok(detail)
     }
 
+    // The fetched thread replaces the cached one, so deleted comments go away.
+    pub fn replace_comments(&self, issue_key: &str, comments: &[Comment]) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM comments WHERE issue_key = ?1",
+            params![issue_key],
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO comments(id, issue_key, author, content_raw, content_md, created_at, updated_at)
+                 VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for c in comments {
+                stmt.execute(params![
+                    c.id,
+                    issue_key,
+                    c.author,
+                    c.content_raw,
+                    c.content_md,
+                    c.created_at,
+                    c.updated_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn list_comments(&self, issue_key: &str) -> AppResult<Vec<Comment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, issue_key, author, content_raw, content_md, created_at, updated_at
+             FROM comments WHERE issue_key = ?1
+             ORDER BY created_at ASC, id ASC",
+        )?;
+        let rows = stmt.query_map(params![issue_key], |row| {
+            Ok(Comment {
+                id: row.get(0)?,
+                issue_key: row.get(1)?,
+                author: row.get(2)?,
+                content_raw: row.get(3)?,
+                content_md: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    // The listing replaces the project's cached pages, so deleted pages go
+    // away. Content fetched earlier is kept; a changed `updated_at` tells
+    // that it is out of date.
+    pub fn replace_wiki_list(&self, project_id: i64, pages: &[WikiSummary]) -> AppResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let ids: Vec<i64> = pages.iter().map(|p| p.id).collect();
+        tx.execute(
+            &format!(
+                "DELETE FROM wikis WHERE project_id = ? AND id NOT IN ({})",
+                placeholders(ids.len())
+            ),
+            params_from_iter(std::iter::once(project_id).chain(ids)),
+        )?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO wikis(id, project_id, name, updated_at)
+                 VALUES(?1, ?2, ?3, ?4)
+                 ON CONFLICT(id) DO UPDATE SET
+                   project_id = excluded.project_id,
+                   name = excluded.name,
+                   updated_at = excluded.updated_at",
+            )?;
+            for page in pages {
+                stmt.execute(params![
+                    page.id,
+                    page.project_id,
+                    page.name,
+                    page.updated_at
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn upsert_wiki_page(&self, page: &WikiPage) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO wikis(id, project_id, name, updated_at, content_raw, content_md, tags, synced_at, conversion_warnings)
+             VALUES(?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(id) DO UPDATE SET
+               project_id = excluded.project_id,
+               name = excluded.name,
+               updated_at = excluded.updated_at,
+               content_raw = excluded.content_raw,
+               content_md = excluded.content_md,
+               tags = excluded.tags,
+               synced_at = excluded.synced_at,
+               conversion_warnings = excluded.conversion_warnings",
+            params![
+                page.id,
+                page.project_id,
+                page.name,
+                page.updated_at,
+                page.content_raw,
+                page.content_md,
+                to_json(&page.tags)?,
+                page.synced_at,
+                to_json(&page.conversion_warnings)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_wikis_local(&self, project_id: i64) -> AppResult<Vec<WikiSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, project_id, name, updated_at
+             FROM wikis WHERE project_id = ?1
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(WikiSummary {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                name: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(row?);
+        }
+        Ok(out)
+    }
+
+    // Pages that were only listed have no content yet and are not returned.
+    pub fn get_wiki_local(&self, wiki_id: i64) -> AppResult<Option<WikiPage>> {
+        let page = self
+            .conn
+            .query_row(
+                "SELECT id, project_id, name, content_raw, content_md, tags, updated_at, synced_at, conversion_warnings
+                 FROM wikis WHERE id = ?1 AND content_raw IS NOT NULL",
+                params![wiki_id],
+                |row| {
+                    Ok(WikiPage {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        content_raw: row.get(3)?,
+                        content_md: row.get::<_, Option<String>>(4)?.unwrap_or_default(),
+                        tags: from_json(&row.get::<_, String>(5)?),
+                        updated_at: row.get(6)?,
+                        synced_at: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
+                        conversion_warnings: from_json(&row.get::<_, String>(8)?),
+                    })
+                },
+            )
+            .optional()?;
+        Ok(page)
+    }
+
     pub fn insert_export_history(&self, issue_key: &str, export_path: &str) -> AppResult<()> {
         self.conn.execute(
             "INSERT INTO exports(issue_key, export_path, exported_at)
@@ -270,15 +818,25 @@ impl Db {
         Ok(())
     }
 
+    // Failed exports have no file; the error explains what went wrong.
+    pub fn insert_export_failure(&self, issue_key: &str, error: &str) -> AppResult<()> {
+        self.conn.execute(
+            "INSERT INTO exports(issue_key, export_path, exported_at, error)
+             VALUES(?1, '', ?2, ?3)",
+            params![issue_key, Utc::now().to_rfc3339(), error],
+        )?;
+        Ok(())
+    }
+
     pub fn list_exports(&self, limit: i64) -> AppResult<Vec<ExportHistory>> {
         if limit <= 0 {
             return Err(AppError::Validation("limit must be > 0".to_string()));
         }
 
         let mut stmt = self.conn.prepare(
-            "SELECT id, issue_key, export_path, exported_at
+            "SELECT id, issue_key, export_path, exported_at, error
              FROM exports
-             ORDER BY exported_at DESC
+             ORDER BY exported_at DESC, id DESC
              LIMIT ?1",
         )?;
         let rows = stmt.query_map(params![limit], |row| {
@@ -287,6 +845,7 @@ impl Db {
                 issue_key: row.get(1)?,
                 export_path: row.get(2)?,
                 exported_at: row.get(3)?,
+                error: row.get(4)?,
             })
         })?;
 
@@ -303,10 +862,40 @@ impl Db {
     }
 }
 
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
+// List columns are stored as JSON arrays.
+fn to_json<T: serde::Serialize>(value: &T) -> AppResult<String> {
+    serde_json::to_string(value).map_err(|e| AppError::Db(e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned + Default>(value: &str) -> T {
+    serde_json::from_str(value).unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use super::Db;
-    use crate::models::IssueSummary;
+    use crate::models::{
+        AuthMethod, Comment, ConversionWarning, FrontMatterField, FrontMatterSettings, IssueDetail,
+        IssueFacets, IssueFilter, IssueListing, IssueSort, IssueSummary, Project, SortOrder,
+        WikiPage, WikiSummary,
+    };
+
+    fn listing(key: &str, summary: &str, facets: IssueFacets) -> IssueListing {
+        IssueListing {
+            summary: IssueSummary {
+                issue_key: key.to_string(),
+                summary: summary.to_string(),
+                updated_at: "2026-01-01T00:00:00Z".to_string(),
+            },
+            created_at: None,
+            due_date: None,
+            facets,
+        }
+    }
 
     #[test]
     fn upsert_and_search_issue_summary() {
@@ -314,12 +903,8 @@ mod tests {
         let db = Db { conn };
         db.init_schema().expect("schema");
 
-        db.upsert_issue_summary(&IssueSummary {
-            issue_key: "PROJ-1".to_string(),
-            summary: "hello world".to_string(),
-            updated_at: "2026-01-01T00:00:00Z".to_string(),
-        })
-        .expect("upsert");
+        db.upsert_issue_listing(&listing("PROJ-1", "hello world", IssueFacets::default()))
+            .expect("upsert");
 
         let found = db
             .search_issue_summaries_local("hello")
@@ -327,4 +912,279 @@ mod tests {
         assert_eq!(found.len(), 1);
         assert_eq!(found[0].issue_key, "PROJ-1");
     }
+
+    #[test]
+    fn schema_upgrade_adds_formatting_rule_column() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        conn.execute_batch(
+            "CREATE TABLE projects (
+                id INTEGER PRIMARY KEY,
+                project_key TEXT NOT NULL,
+                name TEXT NOT NULL,
+                synced_at TEXT NOT NULL
+            );
+            INSERT INTO projects VALUES (1, 'OLD', 'old project', '2026-01-01T00:00:00Z');",
+        )
+        .expect("old schema");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        db.upsert_projects(&[Project {
+            id: 2,
+            project_key: "MD".to_string(),
+            name: "markdown project".to_string(),
+            text_formatting_rule: "markdown".to_string(),
+            synced_at: String::new(),
+        }])
+        .expect("upsert");
+
+        let projects = db.list_projects().expect("list");
+        assert_eq!(projects[0].project_key, "MD");
+        assert_eq!(projects[0].text_formatting_rule, "markdown");
+        assert_eq!(projects[1].project_key, "OLD");
+        assert_eq!(projects[1].text_formatting_rule, "backlog");
+    }
+
+    #[test]
+    fn issue_detail_round_trips_through_cache() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        let warning = ConversionWarning {
+            construct: "{color}".to_string(),
+            line: 3,
+            action: "color removed, text kept".to_string(),
+        };
+        db.upsert_issue_detail(&IssueDetail {
+            issue_key: "PROJ-1".to_string(),
+            summary: "hello".to_string(),
+            description_raw: "{color:red}x{color}".to_string(),
+            description_md: "x".to_string(),
+            updated_at: "2026-01-01T00:00:00Z".to_string(),
+            conversion_warnings: vec![warning.clone()],
+            status: Some("処理中".to_string()),
+            categories: vec!["UI".to_string(), "API".to_string()],
+            parent_issue_key: Some("PROJ-0".to_string()),
+            ..IssueDetail::default()
+        })
+        .expect("upsert");
+
+        let detail = db
+            .get_issue_detail_local("PROJ-1")
+            .expect("load")
+            .expect("cached");
+        assert_eq!(detail.conversion_warnings, vec![warning]);
+        assert_eq!(detail.status.as_deref(), Some("処理中"));
+        assert_eq!(detail.categories, vec!["UI", "API"]);
+        assert_eq!(detail.parent_issue_key.as_deref(), Some("PROJ-0"));
+        assert!(detail.assignee.is_none());
+    }
+
+    #[test]
+    fn replace_comments_keeps_thread_in_order() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        let comment = |id: i64, created_at: &str| Comment {
+            id,
+            issue_key: "PROJ-1".to_string(),
+            author: "山田".to_string(),
+            content_raw: format!("comment {id}"),
+            content_md: format!("comment {id}"),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+        };
+        db.replace_comments(
+            "PROJ-1",
+            &[
+                comment(1, "2026-01-01T00:00:00Z"),
+                comment(2, "2026-01-02T00:00:00Z"),
+            ],
+        )
+        .expect("first sync");
+        db.replace_comments(
+            "PROJ-1",
+            &[
+                comment(3, "2026-01-03T00:00:00Z"),
+                comment(2, "2026-01-02T00:00:00Z"),
+            ],
+        )
+        .expect("second sync");
+
+        let ids: Vec<i64> = db
+            .list_comments("PROJ-1")
+            .expect("list")
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert!(db.list_comments("PROJ-2").expect("list").is_empty());
+    }
+
+    #[test]
+    fn front_matter_settings_default_until_saved() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        assert_eq!(
+            db.load_front_matter().expect("load"),
+            FrontMatterSettings::default()
+        );
+
+        let settings = FrontMatterSettings {
+            enabled: false,
+            fields: vec![FrontMatterField::IssueKey, FrontMatterField::Tags],
+        };
+        db.save_front_matter(&settings).expect("save");
+        assert_eq!(db.load_front_matter().expect("load"), settings);
+    }
+
+    #[test]
+    fn auth_method_defaults_to_api_key() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        assert_eq!(db.load_auth_method().expect("load"), AuthMethod::ApiKey);
+        db.save_auth_method(AuthMethod::OAuth).expect("save");
+        assert_eq!(db.load_auth_method().expect("load"), AuthMethod::OAuth);
+        db.clear_auth_method().expect("clear");
+        assert_eq!(db.load_auth_method().expect("load"), AuthMethod::ApiKey);
+    }
+
+    #[test]
+    fn export_history_records_failures() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        db.insert_export_history("PROJ-1", "/tmp/PROJ-1.md")
+            .expect("success");
+        db.insert_export_failure("PROJ-2", "not found")
+            .expect("failure");
+
+        let history = db.list_exports(10).expect("list");
+        assert_eq!(history.len(), 2);
+        let failed = history
+            .iter()
+            .find(|h| h.issue_key == "PROJ-2")
+            .expect("failure row");
+        assert_eq!(failed.error.as_deref(), Some("not found"));
+        assert_eq!(failed.export_path, "");
+        let exported = history
+            .iter()
+            .find(|h| h.issue_key == "PROJ-1")
+            .expect("success row");
+        assert!(exported.error.is_none());
+    }
+
+    #[test]
+    fn offline_search_applies_filters() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        let issue = |key: &str, created: &str, status: i64, categories: Vec<i64>| IssueListing {
+            created_at: Some(format!("{created}T09:00:00Z")),
+            ..listing(
+                key,
+                &format!("{key} ログイン"),
+                IssueFacets {
+                    project_id: Some(1),
+                    status_id: Some(status),
+                    category_ids: categories,
+                    ..IssueFacets::default()
+                },
+            )
+        };
+        for item in [
+            issue("PROJ-1", "2026-01-05", 1, vec![10]),
+            issue("PROJ-2", "2026-02-10", 2, vec![10, 11]),
+            issue("PROJ-3", "2026-03-15", 2, vec![]),
+            issue("PROJ-4", "2026-03-20", 3, vec![11]),
+        ] {
+            db.upsert_issue_listing(&item).expect("upsert");
+        }
+
+        let keys = |filter: &IssueFilter, count: usize, offset: usize| {
+            let (issues, total) = db
+                .search_issues_local(filter, count, offset)
+                .expect("search");
+            let keys: Vec<String> = issues.into_iter().map(|i| i.issue_key).collect();
+            (keys, total)
+        };
+
+        let filter = IssueFilter {
+            keyword: Some("ログイン".to_string()),
+            project_ids: vec![1],
+            status_ids: vec![2, 3],
+            created_since: Some("2026-02-10".to_string()),
+            created_until: Some("2026-03-20".to_string()),
+            sort: IssueSort::Created,
+            order: SortOrder::Asc,
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            keys(&filter, 20, 0),
+            (vec!["PROJ-2".into(), "PROJ-3".into(), "PROJ-4".into()], 3)
+        );
+        assert_eq!(keys(&filter, 1, 1), (vec!["PROJ-3".into()], 3));
+
+        let by_category = IssueFilter {
+            category_ids: vec![11],
+            sort: IssueSort::Created,
+            order: SortOrder::Desc,
+            ..IssueFilter::default()
+        };
+        assert_eq!(
+            keys(&by_category, 20, 0),
+            (vec!["PROJ-4".into(), "PROJ-2".into()], 2)
+        );
+    }
+
+    #[test]
+    fn wiki_cache_follows_the_listing() {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let db = Db { conn };
+        db.init_schema().expect("schema");
+
+        let summary = |id: i64, name: &str| WikiSummary {
+            id,
+            project_id: 12,
+            name: name.to_string(),
+            updated_at: "2026-01-01T00:00:00Z".to_string(),
+        };
+        db.replace_wiki_list(12, &[summary(1, "Home"), summary(2, "設計/API")])
+            .expect("list");
+        assert_eq!(db.get_wiki_local(2).expect("get"), None);
+
+        let page = WikiPage {
+            id: 2,
+            project_id: 12,
+            name: "設計/API".to_string(),
+            content_raw: "h1. API".to_string(),
+            content_md: "# API".to_string(),
+            tags: vec!["仕様".to_string()],
+            updated_at: "2026-01-02T00:00:00Z".to_string(),
+            synced_at: "2026-01-03T00:00:00Z".to_string(),
+            conversion_warnings: Vec::new(),
+        };
+        db.upsert_wiki_page(&page).expect("upsert");
+        assert_eq!(db.get_wiki_local(2).expect("get"), Some(page.clone()));
+
+        // Listing again keeps fetched content but drops deleted pages.
+        db.replace_wiki_list(12, &[summary(2, "設計/API")])
+            .expect("relist");
+        assert_eq!(
+            db.list_wikis_local(12).expect("list"),
+            vec![summary(2, "設計/API")]
+        );
+        assert_eq!(
+            db.get_wiki_local(2).expect("get").map(|p| p.content_md),
+            Some(page.content_md)
+        );
+    }
 }
@@ -0,0 +1,614 @@
+use crate::app_error::AppResult;
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::models::{
+    Attachment, Comment, FrontMatterField, FrontMatterSettings, IssueDetail, WikiPage,
+};
+use crate::template::{Context, Template};
+
+// Reproduces the built-in layout: front matter, the description, the issue's
+// metadata and the comment thread oldest first, leaving out empty sections.
+pub const DEFAULT_TEMPLATE: &str = "\
+{{#front_matter}}
+{{front_matter}}
+
+{{/front_matter}}
+{{#description}}
+{{description}}
+{{/description}}
+{{#has_metadata}}
+{{#description}}
+
+{{/description}}
+## 課題情報
+
+{{#metadata}}
+- {{label}}: {{value}}
+{{/metadata}}
+{{/has_metadata}}
+{{#has_comments}}
+{{#description}}
+
+{{/description}}
+{{^description}}
+{{#has_metadata}}
+
+{{/has_metadata}}
+{{/description}}
+## コメント
+{{#comments}}
+
+### {{author}} ({{created_at}})
+
+{{body}}
+{{/comments}}
+{{/has_comments}}
+";
+
+pub struct ExportInput<'a> {
+    pub detail: &'a IssueDetail,
+    pub description_md: &'a str,
+    pub comments: &'a [Comment],
+    pub attachments: &'a [Attachment],
+    pub asset_dir: &'a str,
+    pub front_matter: &'a str,
+    pub issue_url: Option<&'a str>,
+    pub exported_at: &'a str,
+}
+
+// Parses a user template and checks that it only refers to fields an export
+// provides, so mistakes surface when the template is saved.
+pub fn parse_template(source: &str) -> AppResult<Template> {
+    let template = Template::parse(source)?;
+    template.validate(&sample_context())?;
+    Ok(template)
+}
+
+pub fn render_issue(template: &Template, input: &ExportInput) -> AppResult<String> {
+    template.render(&issue_context(input))
+}
+
+fn issue_context(input: &ExportInput) -> Context {
+    let detail = input.detail;
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    let names = |values: &[String]| {
+        values
+            .iter()
+            .map(|name| Context::default().text("name", name.as_str()))
+            .collect()
+    };
+
+    let metadata: Vec<Context> = metadata_fields(detail)
+        .into_iter()
+        .map(|(label, value)| Context::default().text("label", label).text("value", value))
+        .collect();
+    let comments: Vec<Context> = input
+        .comments
+        .iter()
+        .map(|comment| {
+            let author = if comment.author.is_empty() {
+                "(不明なユーザー)"
+            } else {
+                &comment.author
+            };
+            Context::default()
+                .text("author", author)
+                .text("created_at", comment.created_at.as_str())
+                .text("updated_at", comment.updated_at.as_str())
+                .text("body", comment.content_md.trim_end())
+        })
+        .collect();
+    let attachments: Vec<Context> = input
+        .attachments
+        .iter()
+        .zip(attachment_file_names(input.attachments))
+        .map(|(attachment, file_name)| {
+            let path = format!("{}/{file_name}", input.asset_dir);
+            Context::default()
+                .text("name", attachment.name.as_str())
+                .text("path", path)
+                .text("size", attachment.size.to_string())
+        })
+        .collect();
+
+    Context::default()
+        .text("front_matter", input.front_matter.trim_end())
+        .text("issue_key", detail.issue_key.as_str())
+        .text("summary", detail.summary.as_str())
+        .text("description", input.description_md.trim_end())
+        .text("url", input.issue_url.unwrap_or_default())
+        .text("updated_at", detail.updated_at.as_str())
+        .text("exported_at", input.exported_at)
+        .text("issue_type", optional(&detail.issue_type))
+        .text("status", optional(&detail.status))
+        .text("priority", optional(&detail.priority))
+        .text("assignee", optional(&detail.assignee))
+        .text("created_user", optional(&detail.created_user))
+        .text("created_at", optional(&detail.created_at))
+        .text("due_date", optional(&detail.due_date))
+        .text("parent_issue_key", optional(&detail.parent_issue_key))
+        .list("categories", names(&detail.categories))
+        .list("versions", names(&detail.versions))
+        .list("milestones", names(&detail.milestones))
+        .flag("has_metadata", !metadata.is_empty())
+        .list("metadata", metadata)
+        .flag("has_comments", !comments.is_empty())
+        .list("comments", comments)
+        .flag("has_attachments", !attachments.is_empty())
+        .list("attachments", attachments)
+}
+
+// An issue with one of everything, so validation reaches the fields inside
+// every list section.
+fn sample_context() -> Context {
+    let one = || vec![String::new()];
+    let detail = IssueDetail {
+        categories: one(),
+        versions: one(),
+        milestones: one(),
+        ..IssueDetail::default()
+    };
+    let comment = Comment {
+        id: 0,
+        issue_key: String::new(),
+        author: String::new(),
+        content_raw: String::new(),
+        content_md: String::new(),
+        created_at: String::new(),
+        updated_at: String::new(),
+    };
+    let attachment = Attachment {
+        id: 0,
+        name: String::new(),
+        size: 0,
+    };
+    let context = issue_context(&ExportInput {
+        detail: &detail,
+        description_md: "",
+        comments: &[comment],
+        attachments: &[attachment],
+        asset_dir: "",
+        front_matter: "",
+        issue_url: None,
+        exported_at: "",
+    });
+    // Empty metadata values are left out of the list, so add an item directly.
+    context.list(
+        "metadata",
+        vec![Context::default().text("label", "").text("value", "")],
+    )
+}
+
+// Attachment names come from the server and must not escape the assets folder.
+pub fn attachment_file_name(name: &str) -> String {
+    let cleaned = name.replace(['/', '\\'], "_");
+    if cleaned.trim_matches('.').is_empty() {
+        "_".to_string()
+    } else {
+        cleaned
+    }
+}
+
+// The names attachments are saved under, in order. Issues can carry several
+// attachments with the same name; the first keeps it, so `#image(name)`
+// still finds a file, and the others get their id appended, plus a counter
+// should that name be taken as well. Names are compared ignoring case, as the
+// macOS file system does.
+pub fn attachment_file_names(attachments: &[Attachment]) -> Vec<String> {
+    let mut taken = HashSet::new();
+    attachments
+        .iter()
+        .map(|attachment| {
+            let name = attachment_file_name(&attachment.name);
+            if taken.insert(name.to_lowercase()) {
+                return name;
+            }
+            let (stem, ext) = match name.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{ext}")),
+                _ => (name.as_str(), String::new()),
+            };
+            let id = attachment.id;
+            let mut unique = format!("{stem}-{id}{ext}");
+            let mut n = 2;
+            while !taken.insert(unique.to_lowercase()) {
+                unique = format!("{stem}-{id}-{n}{ext}");
+                n += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+// `設計/API` is written as `設計/API.md`, so the export mirrors the wiki's
+// page tree. Empty segments are dropped and each segment is cleaned like an
+// attachment name, which keeps `..` from leaving the export directory.
+pub fn wiki_file_path(name: &str) -> PathBuf {
+    let mut segments: Vec<String> = name
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(attachment_file_name)
+        .collect();
+    let file = segments.pop().unwrap_or_else(|| "_".to_string());
+    let mut path: PathBuf = segments.into_iter().collect();
+    path.push(format!("{file}.md"));
+    path
+}
+
+// The page's own name is the title; its parents are already in the path.
+pub fn wiki_document(page: &WikiPage) -> String {
+    let title = page.name.rsplit('/').next().unwrap_or(&page.name);
+    let content = page.content_md.trim_end();
+    if content.is_empty() {
+        format!("# {title}\n")
+    } else {
+        format!("# {title}\n\n{content}\n")
+    }
+}
+
+// YAML front matter for static site generators and note apps. Keys keep the
+// order of `FrontMatterField::ALL`; unknown values are written as `null` so
+// every exported file has the same keys.
+pub fn front_matter(
+    detail: &IssueDetail,
+    settings: &FrontMatterSettings,
+    issue_url: Option<&str>,
+    exported_at: &str,
+) -> String {
+    if !settings.enabled || settings.fields.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::from("---\n");
+    for field in FrontMatterField::ALL {
+        if !settings.fields.contains(&field) {
+            continue;
+        }
+        let value = match field {
+            FrontMatterField::IssueKey => Some(detail.issue_key.as_str()),
+            FrontMatterField::Summary => Some(detail.summary.as_str()),
+            FrontMatterField::Status => detail.status.as_deref(),
+            FrontMatterField::Assignee => detail.assignee.as_deref(),
+            FrontMatterField::Url => issue_url,
+            FrontMatterField::UpdatedAt => Some(detail.updated_at.as_str()),
+            FrontMatterField::ExportedAt => Some(exported_at),
+            FrontMatterField::Tags => {
+                out.push_str("tags:");
+                if detail.categories.is_empty() {
+                    out.push_str(" []\n");
+                } else {
+                    out.push('\n');
+                    for tag in &detail.categories {
+                        out.push_str(&format!("  - {}\n", yaml_string(tag)));
+                    }
+                }
+                continue;
+            }
+        };
+        let value = value.map_or_else(|| "null".to_string(), yaml_string);
+        out.push_str(&format!("{}: {value}\n", field.key()));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+// Every value is double quoted, so text such as `yes`, `123`, `: ` or a
+// leading `#` stays a string. Non-ASCII text is valid UTF-8 YAML as is.
+fn yaml_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{85}' => out.push_str("\\N"),
+            '\u{2028}' => out.push_str("\\L"),
+            '\u{2029}' => out.push_str("\\P"),
+            '\u{feff}' => out.push_str("\\uFEFF"),
+            c if c.is_control() => out.push_str(&format!("\\x{:02X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Labelled metadata values in display order; unset fields are skipped.
+pub fn metadata_fields(detail: &IssueDetail) -> Vec<(&'static str, String)> {
+    let single = [
+        ("種別", &detail.issue_type),
+        ("状態", &detail.status),
+        ("優先度", &detail.priority),
+        ("担当者", &detail.assignee),
+        ("登録者", &detail.created_user),
+        ("登録日時", &detail.created_at),
+        ("期限日", &detail.due_date),
+        ("親課題", &detail.parent_issue_key),
+    ];
+    let lists = [
+        ("カテゴリー", &detail.categories),
+        ("発生バージョン", &detail.versions),
+        ("マイルストーン", &detail.milestones),
+    ];
+
+    let mut fields: Vec<(&'static str, String)> = single
+        .into_iter()
+        .filter_map(|(label, value)| {
+            value
+                .as_deref()
+                .filter(|v| !v.is_empty())
+                .map(|v| (label, v.to_string()))
+        })
+        .collect();
+    fields.extend(
+        lists
+            .into_iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(label, values)| (label, values.join(", "))),
+    );
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        attachment_file_name, attachment_file_names, front_matter, parse_template, render_issue,
+        wiki_file_path, ExportInput, DEFAULT_TEMPLATE,
+    };
+    use crate::models::{Attachment, Comment, FrontMatterField, FrontMatterSettings, IssueDetail};
+
+    fn input<'a>(
+        detail: &'a IssueDetail,
+        description_md: &'a str,
+        comments: &'a [Comment],
+    ) -> ExportInput<'a> {
+        ExportInput {
+            detail,
+            description_md,
+            comments,
+            attachments: &[],
+            asset_dir: "assets/PROJ-1",
+            front_matter: "",
+            issue_url: None,
+            exported_at: "2026-02-01T00:00:00Z",
+        }
+    }
+
+    fn issue_document(detail: &IssueDetail, description_md: &str, comments: &[Comment]) -> String {
+        let template = parse_template(DEFAULT_TEMPLATE).expect("default template");
+        render_issue(&template, &input(detail, description_md, comments)).expect("render")
+    }
+
+    fn comment(author: &str, created_at: &str, body: &str) -> Comment {
+        Comment {
+            id: 1,
+            issue_key: "PROJ-1".to_string(),
+            author: author.to_string(),
+            content_raw: body.to_string(),
+            content_md: body.to_string(),
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn description_without_comments_is_unchanged() {
+        assert_eq!(
+            issue_document(&IssueDetail::default(), "body\n", &[]),
+            "body\n"
+        );
+    }
+
+    #[test]
+    fn comments_follow_the_description() {
+        let comments = [
+            comment("山田", "2026-01-01T09:00:00Z", "確認しました\n"),
+            comment("", "2026-01-02T10:00:00Z", "- done"),
+        ];
+        assert_eq!(
+            issue_document(&IssueDetail::default(), "# Spec\n", &comments),
+            "# Spec\n\n## コメント\n\n### 山田 (2026-01-01T09:00:00Z)\n\n確認しました\n\n### (不明なユーザー) (2026-01-02T10:00:00Z)\n\n- done\n"
+        );
+    }
+
+    #[test]
+    fn metadata_sits_between_description_and_comments() {
+        let detail = IssueDetail {
+            status: Some("処理中".to_string()),
+            assignee: Some("山田".to_string()),
+            due_date: Some(String::new()),
+            categories: vec!["UI".to_string(), "API".to_string()],
+            ..IssueDetail::default()
+        };
+        let comments = [comment("山田", "2026-01-01T09:00:00Z", "確認しました")];
+
+        assert_eq!(
+            issue_document(&detail, "# Spec\n", &comments),
+            "# Spec\n\n## 課題情報\n\n- 状態: 処理中\n- 担当者: 山田\n- カテゴリー: UI, API\n\n## コメント\n\n### 山田 (2026-01-01T09:00:00Z)\n\n確認しました\n"
+        );
+        assert_eq!(
+            issue_document(&detail, "", &[]),
+            "## 課題情報\n\n- 状態: 処理中\n- 担当者: 山田\n- カテゴリー: UI, API\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_quotes_and_escapes_values() {
+        let detail = IssueDetail {
+            issue_key: "PROJ-1".to_string(),
+            summary: "「仕様」: \"yes\" # 確認\\改行\nあり".to_string(),
+            updated_at: "2026-01-01T00:00:00Z".to_string(),
+            status: Some("処理中".to_string()),
+            categories: vec!["UI".to_string(), "- list".to_string()],
+            ..IssueDetail::default()
+        };
+
+        assert_eq!(
+            front_matter(
+                &detail,
+                &FrontMatterSettings::default(),
+                Some("https://team.backlog.com/view/PROJ-1"),
+                "2026-02-01T00:00:00Z",
+            ),
+            "---\n\
+             issue_key: \"PROJ-1\"\n\
+             summary: \"「仕様」: \\\"yes\\\" # 確認\\\\改行\\nあり\"\n\
+             status: \"処理中\"\n\
+             assignee: null\n\
+             url: \"https://team.backlog.com/view/PROJ-1\"\n\
+             updated_at: \"2026-01-01T00:00:00Z\"\n\
+             exported_at: \"2026-02-01T00:00:00Z\"\n\
+             tags:\n  - \"UI\"\n  - \"- list\"\n\
+             ---\n\n"
+        );
+    }
+
+    #[test]
+    fn front_matter_follows_settings() {
+        let detail = IssueDetail {
+            issue_key: "PROJ-1".to_string(),
+            ..IssueDetail::default()
+        };
+        let settings = FrontMatterSettings {
+            enabled: true,
+            fields: vec![FrontMatterField::Tags, FrontMatterField::IssueKey],
+        };
+        assert_eq!(
+            front_matter(&detail, &settings, None, ""),
+            "---\nissue_key: \"PROJ-1\"\ntags: []\n---\n\n"
+        );
+
+        let disabled = FrontMatterSettings {
+            enabled: false,
+            ..settings
+        };
+        assert_eq!(front_matter(&detail, &disabled, None, ""), "");
+    }
+
+    #[test]
+    fn default_template_puts_front_matter_first() {
+        let template = parse_template(DEFAULT_TEMPLATE).expect("default template");
+        let detail = IssueDetail::default();
+        let export = ExportInput {
+            front_matter: "---\nissue_key: \"PROJ-1\"\n---\n\n",
+            ..input(&detail, "body", &[])
+        };
+        assert_eq!(
+            render_issue(&template, &export).expect("render"),
+            "---\nissue_key: \"PROJ-1\"\n---\n\nbody\n"
+        );
+    }
+
+    #[test]
+    fn custom_templates_see_fields_comments_and_attachments() {
+        let template = parse_template(
+            "# {{issue_key}} {{summary}}\n\n\
+             | 状態 | 担当者 |\n|---|---|\n| {{status}} | {{assignee}} |\n\n\
+             {{description}}\n\
+             {{#has_attachments}}\n\n## 添付\n\n{{/has_attachments}}\n\
+             {{#attachments}}\n- [{{name}}](<{{path}}>)\n{{/attachments}}\n",
+        )
+        .expect("valid template");
+        let detail = IssueDetail {
+            issue_key: "PROJ-1".to_string(),
+            summary: "仕様".to_string(),
+            status: Some("完了".to_string()),
+            ..IssueDetail::default()
+        };
+        let attachments = [Attachment {
+            id: 1,
+            name: "画面 1.png".to_string(),
+            size: 10,
+        }];
+        let export = ExportInput {
+            attachments: &attachments,
+            ..input(&detail, "本文\n", &[])
+        };
+
+        assert_eq!(
+            render_issue(&template, &export).expect("render"),
+            "# PROJ-1 仕様\n\n| 状態 | 担当者 |\n|---|---|\n| 完了 |  |\n\n本文\n\n## 添付\n\n- [画面 1.png](<assets/PROJ-1/画面 1.png>)\n"
+        );
+    }
+
+    #[test]
+    fn templates_with_unknown_fields_are_rejected() {
+        let err = parse_template("{{#comments}}\n{{body}} {{reporter}}\n{{/comments}}")
+            .expect_err("unknown field");
+        assert_eq!(
+            err.to_string(),
+            "validation error: template line 2: unknown field `reporter`"
+        );
+    }
+
+    #[test]
+    fn attachment_names_stay_inside_assets_folder() {
+        assert_eq!(attachment_file_name("画面 1.png"), "画面 1.png");
+        assert_eq!(attachment_file_name("../../etc/passwd"), ".._.._etc_passwd");
+        assert_eq!(attachment_file_name(".."), "_");
+    }
+
+    #[test]
+    fn attachments_with_the_same_name_get_separate_files() {
+        let attachment = |id: i64, name: &str| Attachment {
+            id,
+            name: name.to_string(),
+            size: 1,
+        };
+        let attachments = [
+            attachment(1, "screen.png"),
+            attachment(2, "log"),
+            attachment(3, "Screen.PNG"),
+            attachment(4, "log"),
+            attachment(5, "a/b.txt"),
+            attachment(6, "a_b.txt"),
+        ];
+        assert_eq!(
+            attachment_file_names(&attachments),
+            [
+                "screen.png",
+                "log",
+                "Screen-3.PNG",
+                "log-4",
+                "a_b.txt",
+                "a_b-6.txt"
+            ]
+        );
+
+        // A suffixed name can clash with a real one, and then again.
+        let attachments = [
+            attachment(1, "a.png"),
+            attachment(5, "a-2.png"),
+            attachment(2, "a.png"),
+        ];
+        assert_eq!(
+            attachment_file_names(&attachments),
+            ["a.png", "a-2.png", "a-2-2.png"]
+        );
+        let attachments = [
+            attachment(1, "a.png"),
+            attachment(5, "a-2.png"),
+            attachment(6, "a-2-2.png"),
+            attachment(2, "a.png"),
+        ];
+        assert_eq!(
+            attachment_file_names(&attachments),
+            ["a.png", "a-2.png", "a-2-2.png", "a-2-3.png"]
+        );
+    }
+
+    #[test]
+    fn wiki_pages_map_onto_nested_files() {
+        let path = |name: &str| wiki_file_path(name).to_string_lossy().replace('\\', "/");
+
+        assert_eq!(path("Home"), "Home.md");
+        assert_eq!(path("設計/API/認証"), "設計/API/認証.md");
+        assert_eq!(path("/設計//API/"), "設計/API.md");
+        assert_eq!(path("../secret"), "_/secret.md");
+        assert_eq!(path(""), "_.md");
+    }
+}
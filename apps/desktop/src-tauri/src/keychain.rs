@@ -1,17 +1,34 @@
 use keyring::Entry;
 
-use crate::app_error::AppResult;
+use crate::app_error::{AppError, AppResult};
+use crate::oauth::OAuthCredentials;
+use crate::profiles::FIRST_PROFILE_ID;
 
 const SERVICE: &str = "com.company.backlog-markdown-exporter";
 const USERNAME: &str = "backlog-api-key";
+const OAUTH_USERNAME_PREFIX: &str = "backlog-oauth:";
 
-pub fn save_api_key(api_key: &str) -> AppResult<()> {
-    Entry::new(SERVICE, USERNAME)?.set_password(api_key.trim())?;
+// Every profile has its own entries. The first profile keeps the account
+// names from before there were profiles.
+fn account(name: &str, profile_id: i64) -> String {
+    if profile_id == FIRST_PROFILE_ID {
+        name.to_string()
+    } else {
+        format!("{name}#{profile_id}")
+    }
+}
+
+fn api_key_entry(profile_id: i64) -> AppResult<Entry> {
+    Ok(Entry::new(SERVICE, &account(USERNAME, profile_id))?)
+}
+
+pub fn save_api_key(profile_id: i64, api_key: &str) -> AppResult<()> {
+    api_key_entry(profile_id)?.set_password(api_key.trim())?;
     Ok(())
 }
 
-pub fn load_api_key() -> AppResult<Option<String>> {
-    let entry = Entry::new(SERVICE, USERNAME)?;
+pub fn load_api_key(profile_id: i64) -> AppResult<Option<String>> {
+    let entry = api_key_entry(profile_id)?;
     match entry.get_password() {
         Ok(v) => {
             let trimmed = v.trim().to_string();
@@ -26,10 +43,65 @@ pub fn load_api_key() -> AppResult<Option<String>> {
     }
 }
 
-pub fn delete_api_key() -> AppResult<()> {
-    let entry = Entry::new(SERVICE, USERNAME)?;
+pub fn delete_api_key(profile_id: i64) -> AppResult<()> {
+    let entry = api_key_entry(profile_id)?;
     match entry.delete_credential() {
         Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
         Err(e) => Err(e.into()),
     }
 }
+
+// OAuth credentials are kept per space, so switching spaces does not reuse
+// another space's tokens.
+fn oauth_entry(profile_id: i64, space_url: &str) -> AppResult<Entry> {
+    let space = space_url.trim().trim_end_matches('/');
+    Ok(Entry::new(
+        SERVICE,
+        &account(&format!("{OAUTH_USERNAME_PREFIX}{space}"), profile_id),
+    )?)
+}
+
+pub fn save_oauth_credentials(
+    profile_id: i64,
+    space_url: &str,
+    credentials: &OAuthCredentials,
+) -> AppResult<()> {
+    let json = serde_json::to_string(credentials).map_err(|e| AppError::Keychain(e.to_string()))?;
+    oauth_entry(profile_id, space_url)?.set_password(&json)?;
+    Ok(())
+}
+
+pub fn load_oauth_credentials(
+    profile_id: i64,
+    space_url: &str,
+) -> AppResult<Option<OAuthCredentials>> {
+    match oauth_entry(profile_id, space_url)?.get_password() {
+        Ok(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| AppError::Keychain(e.to_string())),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn delete_oauth_credentials(profile_id: i64, space_url: &str) -> AppResult<()> {
+    match oauth_entry(profile_id, space_url)?.delete_credential() {
+        Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::account;
+    use crate::profiles::FIRST_PROFILE_ID;
+
+    #[test]
+    fn first_profile_keeps_the_original_account_names() {
+        assert_eq!(
+            account("backlog-api-key", FIRST_PROFILE_ID),
+            "backlog-api-key"
+        );
+        assert_eq!(account("backlog-api-key", 3), "backlog-api-key#3");
+    }
+}
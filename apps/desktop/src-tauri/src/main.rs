@@ -4,9 +4,17 @@ mod app_error;
 mod backlog;
 mod commands;
 mod db;
+mod export;
 mod keychain;
 mod markdown;
+#[cfg(test)]
+mod mock_backlog;
 mod models;
+mod oauth;
+mod profiles;
+mod redact;
+mod template;
+mod throttle;
 
 use commands::AppState;
 
@@ -21,12 +29,29 @@ fn main() {
             commands::projects_sync,
             commands::issues_search_by_key,
             commands::issues_search_by_keyword,
+            commands::issues_search,
+            commands::search_filter_options,
             commands::issue_get_detail,
+            commands::issues_conversion_report,
             commands::issue_export_markdown,
+            commands::issues_export_bulk,
+            commands::wikis_list,
+            commands::wiki_get,
+            commands::wikis_export,
+            commands::markdown_to_backlog,
             commands::exports_list,
             commands::exports_clear,
             commands::set_export_dir,
+            commands::set_front_matter,
+            commands::export_template_get,
+            commands::set_export_template,
+            commands::rate_limit_status,
             commands::auth_reset,
+            commands::profiles_list,
+            commands::profile_create,
+            commands::profile_rename,
+            commands::profile_switch,
+            commands::profile_delete,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
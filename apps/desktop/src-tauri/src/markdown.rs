@@ -1,41 +1,138 @@
-use regex::Regex;
+mod ast;
+mod commonmark;
+mod lexer;
+mod notation;
+mod parser;
+mod render;
 
-pub fn backlog_to_markdown(input: &str) -> String {
-    let mut out = input.to_string();
+use crate::models::{ConversionWarning, Project};
 
-    let heading_rules = [(r"(?m)^h1\.\s+", "# "), (r"(?m)^h2\.\s+", "## "), (r"(?m)^h3\.\s+", "### ")];
-    for (pattern, replace) in heading_rules {
-        let re = Regex::new(pattern).expect("valid regex");
-        out = re.replace_all(&out, replace).to_string();
+// Context from the space an issue belongs to. Without it, constructs that need
+// a URL (such as wiki links) degrade to plain text.
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    pub space_url: Option<String>,
+    pub project_key: Option<String>,
+    pub project_keys: Vec<String>,
+    pub issue_links: IssueLinkStyle,
+    pub formatting_rule: TextFormattingRule,
+    // Relative directory the issue's attachments are saved in, if any.
+    pub asset_dir: Option<String>,
+}
+
+// Where a mention like `PROJ-123` of a known project should point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IssueLinkStyle {
+    #[default]
+    Web,
+    File,
+}
+
+// The project's `textFormattingRule`. Text of `markdown` projects is already
+// Markdown and must not go through the Backlog notation parser.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextFormattingRule {
+    #[default]
+    Backlog,
+    Markdown,
+}
+
+impl TextFormattingRule {
+    pub fn from_api(value: &str) -> Self {
+        if value.eq_ignore_ascii_case("markdown") {
+            TextFormattingRule::Markdown
+        } else {
+            TextFormattingRule::Backlog
+        }
     }
+}
+
+impl ConvertOptions {
+    pub fn for_issue(space_url: &str, issue_key: &str, projects: &[Project]) -> Self {
+        Self::for_project(space_url, project_key_of(issue_key).as_deref(), projects)
+    }
+
+    pub fn for_project(space_url: &str, project_key: Option<&str>, projects: &[Project]) -> Self {
+        let formatting_rule = projects
+            .iter()
+            .find(|p| Some(p.project_key.as_str()) == project_key)
+            .map(|p| TextFormattingRule::from_api(&p.text_formatting_rule))
+            .unwrap_or_default();
+
+        Self {
+            space_url: Some(space_url.to_string()),
+            project_key: project_key.map(str::to_string),
+            project_keys: projects.iter().map(|p| p.project_key.clone()).collect(),
+            issue_links: IssueLinkStyle::Web,
+            formatting_rule,
+            asset_dir: None,
+        }
+    }
+}
+
+// The converted text together with the Backlog constructs that could not be
+// carried over as they were.
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    pub markdown: String,
+    pub warnings: Vec<ConversionWarning>,
+}
 
-    let list_re = Regex::new(r"(?m)^\*\s+").expect("valid regex");
-    out = list_re.replace_all(&out, "- ").to_string();
+pub fn convert(input: &str, options: &ConvertOptions) -> Conversion {
+    if options.formatting_rule == TextFormattingRule::Markdown {
+        return Conversion {
+            markdown: input.replace("\r\n", "\n"),
+            warnings: Vec::new(),
+        };
+    }
 
-    let inline_code_re = Regex::new(r"\{\{\s*(.*?)\s*\}\}").expect("valid regex");
-    out = inline_code_re.replace_all(&out, "`$1`").to_string();
+    let document = parser::parse(input, options);
+    Conversion {
+        markdown: render::render_document(&document, options),
+        warnings: document.warnings,
+    }
+}
 
-    // Fallback flattening for common unsupported wiki decorations.
-    let unsupported_rules = [
-        (r"\[\[(.*?)\]\]", "$1"),
-        (r"\{color:[^}]*\}", ""),
-        (r"\{color\}", ""),
-        (r"\{quote\}", ""),
-        (r"\{\{", ""),
-        (r"\}\}", ""),
-    ];
+pub fn backlog_to_markdown(input: &str, options: &ConvertOptions) -> String {
+    convert(input, options).markdown
+}
 
-    for (pattern, replace) in unsupported_rules {
-        let re = Regex::new(pattern).expect("valid regex");
-        out = re.replace_all(&out, replace).to_string();
+// The reverse direction, for drafting issue text in Markdown. Links into the
+// space (issues, wiki pages) are turned back into their Backlog forms.
+pub fn markdown_to_backlog(input: &str, options: &ConvertOptions) -> String {
+    if options.formatting_rule == TextFormattingRule::Markdown {
+        return input.replace("\r\n", "\n");
     }
 
-    out
+    let document = commonmark::parse(input, options);
+    notation::render_document(&document)
+}
+
+fn project_key_of(issue_key: &str) -> Option<String> {
+    issue_key
+        .rsplit_once('-')
+        .map(|(project_key, _)| project_key.to_string())
+        .filter(|project_key| !project_key.is_empty())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::backlog_to_markdown;
+    use super::{ConvertOptions, IssueLinkStyle};
+    use crate::models::Project;
+
+    fn backlog_to_markdown(input: &str) -> String {
+        super::backlog_to_markdown(input, &ConvertOptions::default())
+    }
+
+    fn project(key: &str, rule: &str) -> Project {
+        Project {
+            id: 1,
+            project_key: key.to_string(),
+            name: key.to_string(),
+            text_formatting_rule: rule.to_string(),
+            synced_at: String::new(),
+        }
+    }
 
     #[test]
     fn converts_supported_syntax() {
@@ -54,4 +151,317 @@ mod tests {
         let md = backlog_to_markdown(input);
         assert_eq!(md, "warn and link text");
     }
+
+    #[test]
+    fn block_syntax_only_applies_at_line_start() {
+        let input = "see h1. and * here\n{{ h1. * x }}";
+        let md = backlog_to_markdown(input);
+        assert_eq!(md, "see h1. and * here\n`h1. * x`");
+    }
+
+    #[test]
+    fn code_spans_escape_backticks() {
+        assert_eq!(backlog_to_markdown("{{ a`b }}"), "``a`b``");
+    }
+
+    #[test]
+    fn unmatched_markers_are_kept_as_text() {
+        assert_eq!(
+            backlog_to_markdown("{{ open and ]] close"),
+            "{{ open and ]] close"
+        );
+    }
+
+    #[test]
+    fn plain_text_is_escaped() {
+        assert_eq!(backlog_to_markdown("1. not a list"), "1\\. not a list");
+        assert_eq!(backlog_to_markdown("2) nor this"), "2\\) nor this");
+        assert_eq!(
+            backlog_to_markdown("2026.10.15 release"),
+            "2026.10.15 release"
+        );
+        assert_eq!(
+            backlog_to_markdown("*foo* and a * b"),
+            "\\*foo\\* and a * b"
+        );
+        assert_eq!(
+            backlog_to_markdown("_a_ and snake_case"),
+            "\\_a\\_ and snake_case"
+        );
+        assert_eq!(backlog_to_markdown("#tag and #other"), "\\#tag and #other");
+        assert_eq!(backlog_to_markdown("[x](y)"), "\\[x](y)");
+        assert_eq!(
+            backlog_to_markdown("title\n===\n- not listed\n---"),
+            "title\n\\===\n\\- not listed\n\\---"
+        );
+        assert_eq!(
+            backlog_to_markdown("`tick` <b> ~~x~~ C:\\dir"),
+            "\\`tick\\` \\<b> \\~\\~x\\~\\~ C:\\\\dir"
+        );
+        assert_eq!(backlog_to_markdown("* 1. item"), "- 1\\. item");
+    }
+
+    #[test]
+    fn converts_quote_regions_to_blockquotes() {
+        let input =
+            "before\n{quote}\nDear team,\n\n* point\n{code}\nx = 1\n{/code}\n{/quote}\nafter";
+        let expected = "before\n> Dear team,\n>\n> - point\n> ```\n> x = 1\n> ```\n\nafter";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn converts_line_quotes() {
+        let input = "> quoted ''text''\n>> nested\nreply";
+        let expected = "> quoted **text**\n> > nested\n\nreply";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn converts_tables_with_header_row() {
+        let input = "|Name|Note|h\n|a|x {{p|q}}|\n|b|";
+        let expected = "| Name | Note |\n| --- | --- |\n| a | x `p\\|q` |\n| b |  |";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn tables_without_header_get_an_empty_one() {
+        let input = "intro\n|1|2|\nafter";
+        let expected = "intro\n|  |  |\n| --- | --- |\n| 1 | 2 |\nafter";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn table_cell_line_breaks_become_br() {
+        let input = "|first&br;second|multi\nline|";
+        let expected = "|  |  |\n| --- | --- |\n| first<br>second | multi<br>line |";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn converts_code_blocks_to_fences() {
+        let input = "h2. Sample\n{code:java}\n* ptr {{ x }}\nh1. not a heading\n{/code}\n* item";
+        let expected = "## Sample\n```java\n* ptr {{ x }}\nh1. not a heading\n```\n- item";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn code_fence_outgrows_backticks_in_content() {
+        let input = "{code}\n```\nnested\n```{/code} after";
+        let expected = "````\n```\nnested\n```\n````\nafter";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn unclosed_code_marker_is_plain_text() {
+        assert_eq!(backlog_to_markdown("{code}\n* item"), "{code}\n- item");
+    }
+
+    #[test]
+    fn converts_inline_emphasis() {
+        let input = "''bold'' '''italic''' %%gone%% '''''both'''''";
+        let expected = "**bold** *italic* ~~gone~~ ***both***";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn emphasis_handles_adjacency_and_spacing() {
+        assert_eq!(backlog_to_markdown("x''a''''b''y"), "x**ab**y");
+        assert_eq!(backlog_to_markdown("a'' spaced ''b"), "a **spaced** b");
+        assert_eq!(
+            backlog_to_markdown("''「重要」''です"),
+            "<strong>「重要」</strong>です"
+        );
+    }
+
+    #[test]
+    fn unmatched_emphasis_is_literal() {
+        assert_eq!(backlog_to_markdown("it''s 100%% ok"), "it''s 100%% ok");
+        assert_eq!(backlog_to_markdown("{{ ''raw'' }}"), "`''raw''`");
+    }
+
+    #[test]
+    fn converts_nested_and_ordered_lists() {
+        let input =
+            "* fruit\n** apple\n*** fuji\n** pear\n* steps\n++ wash\n++ cut\n+ first\n+ second";
+        let expected = "- fruit\n  - apple\n    - fuji\n  - pear\n- steps\n  1. wash\n  2. cut\n1. first\n2. second";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn list_items_continue_on_indented_lines() {
+        let input = "+ one\n  more of one\n+ two\nafter";
+        let expected = "1. one\n   more of one\n2. two\n\nafter";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn list_markers_need_whitespace() {
+        assert_eq!(
+            backlog_to_markdown("+1 vote\n*not a list"),
+            "+1 vote\n\\*not a list"
+        );
+    }
+
+    #[test]
+    fn converts_links() {
+        let input = "[[Spec>https://example.com/a b]] and [[Mail:mailto:x@example.com]] [[https://example.com]]";
+        let expected = "[Spec](https://example.com/a%20b) and [Mail](mailto:x@example.com) <https://example.com>";
+        assert_eq!(backlog_to_markdown(input), expected);
+    }
+
+    #[test]
+    fn wiki_links_point_at_the_space_wiki() {
+        let options = ConvertOptions::for_issue("https://team.backlog.com/", "PROJ-1", &[]);
+        let md = super::backlog_to_markdown("[[Home]] / [[仕様>設計/API]]", &options);
+        assert_eq!(
+            md,
+            "[Home](https://team.backlog.com/wiki/PROJ/Home) / [仕様](https://team.backlog.com/wiki/PROJ/%E8%A8%AD%E8%A8%88%2FAPI)"
+        );
+    }
+
+    #[test]
+    fn links_issue_keys_of_known_projects() {
+        let options = ConvertOptions::for_issue(
+            "https://team.backlog.com",
+            "PROJ-1",
+            &[project("PROJ", "backlog"), project("OPS_2", "backlog")],
+        );
+        let input = "PROJ-12とOPS_2-3を参照, not XPROJ-1 / OTHER-4 / {{PROJ-5}} / https://x/PROJ-6";
+        let expected = "[PROJ-12](https://team.backlog.com/view/PROJ-12)と[OPS_2-3](https://team.backlog.com/view/OPS_2-3)を参照, not XPROJ-1 / OTHER-4 / `PROJ-5` / https://x/PROJ-6";
+        assert_eq!(super::backlog_to_markdown(input, &options), expected);
+    }
+
+    #[test]
+    fn issue_keys_can_link_to_exported_files() {
+        let mut options = ConvertOptions::for_issue(
+            "https://team.backlog.com",
+            "PROJ-1",
+            &[project("PROJ", "backlog")],
+        );
+        options.issue_links = IssueLinkStyle::File;
+        assert_eq!(
+            super::backlog_to_markdown("''PROJ-7''", &options),
+            "**[PROJ-7](PROJ-7.md)**"
+        );
+    }
+
+    #[test]
+    fn markdown_projects_pass_through_untouched() {
+        let projects = [project("MD", "markdown"), project("BL", "backlog")];
+        let input = "* item\r\n{{ template }} ''quoted''";
+
+        let md_options = ConvertOptions::for_issue("https://team.backlog.com", "MD-1", &projects);
+        assert_eq!(
+            super::backlog_to_markdown(input, &md_options),
+            "* item\n{{ template }} ''quoted''"
+        );
+
+        let backlog_options =
+            ConvertOptions::for_issue("https://team.backlog.com", "BL-1", &projects);
+        assert_eq!(
+            super::backlog_to_markdown(input, &backlog_options),
+            "- item\n\n`template` **quoted**"
+        );
+    }
+
+    #[test]
+    fn conversion_reports_dropped_formatting() {
+        let input = "h2. Status\n{color:red}late{color}\n{code}\nno close";
+        let conversion = super::convert(input, &ConvertOptions::default());
+        assert_eq!(conversion.markdown, "## Status\nlate\n{code}\nno close");
+
+        let warnings: Vec<String> = conversion
+            .warnings
+            .iter()
+            .map(|w| format!("{}:{}:{}", w.line, w.construct, w.action))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "2:{color}:color removed, text kept",
+                "3:{code}:unclosed code block kept as text",
+            ]
+        );
+
+        let linked = ConvertOptions::for_issue("https://team.backlog.com", "PROJ-1", &[]);
+        assert!(super::convert("[[Home]]", &linked).warnings.is_empty());
+        assert_eq!(
+            super::convert("[[Home]]", &ConvertOptions::default()).warnings[0].construct,
+            "[[wiki link]]"
+        );
+    }
+
+    fn assert_round_trip(input: &str) {
+        let options = ConvertOptions::for_issue(
+            "https://team.backlog.com",
+            "PROJ-1",
+            &[project("PROJ", "backlog")],
+        );
+        let md = super::backlog_to_markdown(input, &options);
+        assert_eq!(
+            super::markdown_to_backlog(&md, &options),
+            input,
+            "via {md:?}"
+        );
+    }
+
+    #[test]
+    fn round_trips_block_constructs() {
+        assert_round_trip("h1. Title\nh3. Sub\ntext\n\nh2. Next");
+        assert_round_trip("* a\n  continued\n** b\n*** c\n* d\n+ one\n++ two\n+ three");
+        assert_round_trip("{code:java}\nint x = 1;\n  * not a list\n{/code}\n{code}\n```\n{/code}");
+        assert_round_trip("|Name|Note|h\n|a|{{b}}|\n|c&br;d|e|");
+        assert_round_trip("|1|2|");
+        assert_round_trip("1. *foo* _a_\n#tag [x](y)\n* - item");
+        assert_round_trip("{quote}\nquoted\n* item\n{/quote}");
+    }
+
+    #[test]
+    fn round_trips_inline_constructs() {
+        assert_round_trip("''bold'' '''italic''' %%strike%% '''''both''''' {{code}}");
+        assert_round_trip("''a '''b''' c'' and ''「重要」''です");
+        assert_round_trip(
+            "[[Docs>https://example.com/a b]] [[https://example.com]] [[mailto:x@example.com]]",
+        );
+        assert_round_trip("[[Home]] [[仕様>設計/API]] PROJ-12 and OTHER-3");
+    }
+
+    #[test]
+    fn converts_markdown_drafts() {
+        let input = "# Plan\n\n1. first\n   - detail\n\n```rust\nfn main() {}\n```\n| A | B |\n|---|:-:|\n| 1 | x \\| y |\n\nSee [docs](https://x.example/a) and `cfg`, __strong__ _em_ ~~old~~ snake_case.";
+        let expected = "h1. Plan\n\n+ first\n** detail\n\n{code:rust}\nfn main() {}\n{/code}\n|A|B|h\n|1|x &#124; y|\n\nSee [[docs>https://x.example/a]] and {{cfg}}, ''strong'' '''em''' %%old%% snake_case.";
+        assert_eq!(
+            super::markdown_to_backlog(input, &ConvertOptions::default()),
+            expected
+        );
+
+        // The escaped pipe stays inside its cell on the way back.
+        assert_eq!(
+            backlog_to_markdown("|1|x &#124; y|"),
+            "|  |  |\n| --- | --- |\n| 1 | x &#124; y |"
+        );
+    }
+
+    #[test]
+    fn image_macros_point_at_saved_attachments() {
+        let input = "#image(screen shot.png) and #thumbnail(図.jpg)";
+        assert_eq!(backlog_to_markdown(input), format!("\\{input}"));
+
+        let mut options = ConvertOptions::for_issue("https://team.backlog.com", "PROJ-1", &[]);
+        options.asset_dir = Some("assets/PROJ-1".to_string());
+        let md = super::backlog_to_markdown(input, &options);
+        assert_eq!(
+            md,
+            "![screen shot.png](assets/PROJ-1/screen%20shot.png) and ![図.jpg](assets/PROJ-1/図.jpg)"
+        );
+        assert_eq!(
+            super::markdown_to_backlog(&md, &options),
+            "#image(screen shot.png) and #image(図.jpg)"
+        );
+
+        assert_eq!(
+            super::backlog_to_markdown("#image(a/b.png) #image(../x.png)", &options),
+            "![a/b.png](assets/PROJ-1/a_b.png) ![../x.png](assets/PROJ-1/.._x.png)"
+        );
+    }
 }
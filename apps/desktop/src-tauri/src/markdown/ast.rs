@@ -0,0 +1,76 @@
+use crate::models::ConversionWarning;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub blocks: Vec<Block>,
+    pub warnings: Vec<ConversionWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading {
+        level: usize,
+        content: Vec<Inline>,
+    },
+    List(List),
+    Quote(Vec<Block>),
+    Code {
+        language: Option<String>,
+        lines: Vec<String>,
+    },
+    Table(Vec<TableRow>),
+    Line(Vec<Inline>),
+    Blank,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct List {
+    pub ordered: bool,
+    pub items: Vec<ListItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    pub lines: Vec<Vec<Inline>>,
+    pub children: Vec<List>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    pub header: bool,
+    pub cells: Vec<Vec<Inline>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Code(String),
+    Link {
+        text: String,
+        target: LinkTarget,
+    },
+    Color(Vec<Inline>),
+    // An attached image, referenced by file name.
+    Image {
+        name: String,
+        thumbnail: bool,
+    },
+    Emphasis {
+        style: EmphasisStyle,
+        children: Vec<Inline>,
+    },
+    LineBreak,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkTarget {
+    Url(String),
+    WikiPage(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmphasisStyle {
+    Bold,
+    Italic,
+    Strike,
+}
@@ -0,0 +1,668 @@
+use super::ast::{Block, Document, EmphasisStyle, Inline, LinkTarget, TableRow};
+use super::parser::{build_lists, push_text, ListEntry};
+use super::ConvertOptions;
+
+// Parses the Markdown subset that has a Backlog counterpart: ATX headings,
+// lists, fenced code, blockquotes, GFM tables, links and emphasis. Anything
+// else is kept as text.
+pub fn parse(input: &str, options: &ConvertOptions) -> Document {
+    let normalized = input.replace("\r\n", "\n");
+    let lines: Vec<&str> = normalized.split('\n').collect();
+    let parser = MarkdownParser { options };
+    Document {
+        blocks: parser.parse_blocks(&lines),
+        warnings: Vec::new(),
+    }
+}
+
+struct MarkdownParser<'a> {
+    options: &'a ConvertOptions,
+}
+
+impl MarkdownParser<'_> {
+    fn parse_blocks(&self, lines: &[&str]) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut pos = 0;
+
+        while pos < lines.len() {
+            let rest = &lines[pos..];
+            let line = rest[0];
+
+            if let Some((block, consumed)) = parse_fence(rest) {
+                blocks.push(block);
+                pos += consumed;
+                continue;
+            }
+
+            if line.trim_start().starts_with('>') {
+                let (block, consumed) = self.parse_quote(rest);
+                blocks.push(block);
+                pos += consumed;
+                continue;
+            }
+
+            if let Some((block, consumed)) = self.parse_table(rest) {
+                blocks.push(block);
+                pos += consumed;
+                continue;
+            }
+
+            // Backlog has no horizontal rule; `---` is kept as text.
+            if !is_thematic_break(line) && list_marker(line).is_some() {
+                let (lists, consumed) = self.parse_lists(rest);
+                blocks.extend(lists);
+                pos += consumed;
+                continue;
+            }
+
+            pos += 1;
+            if line.trim().is_empty() {
+                blocks.push(Block::Blank);
+            } else if let Some((level, content)) = heading(line) {
+                blocks.push(Block::Heading {
+                    level,
+                    content: self.parse_inline(content),
+                });
+            } else if is_thematic_break(line) {
+                blocks.push(Block::Line(vec![Inline::Text(line.trim().to_string())]));
+            } else {
+                blocks.push(Block::Line(self.parse_inline(line)));
+            }
+        }
+
+        blocks
+    }
+
+    fn parse_quote(&self, lines: &[&str]) -> (Block, usize) {
+        let inner: Vec<&str> = lines
+            .iter()
+            .map_while(|line| line.trim_start().strip_prefix('>'))
+            .map(|rest| rest.strip_prefix(' ').unwrap_or(rest))
+            .collect();
+        let consumed = inner.len();
+        (Block::Quote(self.parse_blocks(&inner)), consumed)
+    }
+
+    // The nesting depth of an item follows its indentation: an item is a
+    // child of the previous one when it starts at or past that item's content.
+    fn parse_lists(&self, lines: &[&str]) -> (Vec<Block>, usize) {
+        let mut entries: Vec<ListEntry> = Vec::new();
+        let mut content_columns: Vec<usize> = Vec::new();
+        let mut consumed = 0;
+
+        for line in lines {
+            if let Some(marker) = list_marker(line) {
+                while content_columns
+                    .last()
+                    .is_some_and(|col| marker.indent < *col)
+                {
+                    content_columns.pop();
+                }
+                entries.push(ListEntry {
+                    depth: content_columns.len() + 1,
+                    ordered: marker.ordered,
+                    lines: vec![self.parse_inline(marker.content)],
+                });
+                content_columns.push(marker.content_column);
+            } else if line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+                let content = self.parse_inline(line.trim());
+                match entries.last_mut() {
+                    Some(entry) => entry.lines.push(content),
+                    None => break,
+                }
+            } else {
+                break;
+            }
+            consumed += 1;
+        }
+
+        let lists = build_lists(entries, 1).into_iter().map(Block::List);
+        (lists.collect(), consumed)
+    }
+
+    // Only tables written with a leading pipe are recognised. An all-empty
+    // header row is dropped, since Backlog tables need no header.
+    fn parse_table(&self, lines: &[&str]) -> Option<(Block, usize)> {
+        let (first, second) = (lines.first()?, lines.get(1)?);
+        if !first.trim_start().starts_with('|') || !is_delimiter_row(second) {
+            return None;
+        }
+
+        let mut rows = Vec::new();
+        let header = self.table_cells(first);
+        if header.iter().any(|cell| !cell.is_empty()) {
+            rows.push(TableRow {
+                header: true,
+                cells: header,
+            });
+        }
+
+        let mut consumed = 2;
+        while let Some(line) = lines
+            .get(consumed)
+            .filter(|l| l.trim_start().starts_with('|'))
+        {
+            rows.push(TableRow {
+                header: false,
+                cells: self.table_cells(line),
+            });
+            consumed += 1;
+        }
+        Some((Block::Table(rows), consumed))
+    }
+
+    fn table_cells(&self, line: &str) -> Vec<Vec<Inline>> {
+        split_table_row(line)
+            .into_iter()
+            .map(|cell| self.parse_inline(cell.trim().replace("\\|", "|").as_str()))
+            .collect()
+    }
+
+    fn parse_inline(&self, text: &str) -> Vec<Inline> {
+        let mut out = Vec::new();
+        let mut pos = 0;
+
+        while let Some(c) = text[pos..].chars().next() {
+            match self.inline_at(text, pos) {
+                Some((Inline::Text(literal), len)) => {
+                    push_text(&mut out, &literal);
+                    pos += len;
+                }
+                Some((inline, len)) => {
+                    out.push(inline);
+                    pos += len;
+                }
+                None => {
+                    let mut buf = [0; 4];
+                    push_text(&mut out, c.encode_utf8(&mut buf));
+                    pos += c.len_utf8();
+                }
+            }
+        }
+        out
+    }
+
+    // Returns the inline starting at `pos` and its length in bytes, or `None`
+    // when the character there is plain text.
+    fn inline_at(&self, text: &str, pos: usize) -> Option<(Inline, usize)> {
+        let rest = &text[pos..];
+        match rest.chars().next()? {
+            '\\' => {
+                let escaped = rest[1..]
+                    .chars()
+                    .next()
+                    .filter(char::is_ascii_punctuation)?;
+                Some((Inline::Text(escaped.to_string()), 2))
+            }
+            '`' => Some(code_span(rest)),
+            '<' => self.html_inline(rest),
+            '!' if rest.starts_with("![") => {
+                // Only saved attachments have a notation; other images are kept
+                // as written.
+                let (_, url, len) = link_parts(&rest[1..])?;
+                let inline = match self.attachment_name(url) {
+                    Some(name) => Inline::Image {
+                        name,
+                        thumbnail: false,
+                    },
+                    None => Inline::Text(rest[..len + 1].to_string()),
+                };
+                Some((inline, len + 1))
+            }
+            '[' => {
+                let (label, url, len) = link_parts(rest)?;
+                Some((self.link(unescape(label), url), len))
+            }
+            '*' | '_' | '~' => self.emphasis(text, pos),
+            _ => None,
+        }
+    }
+
+    fn html_inline(&self, rest: &str) -> Option<(Inline, usize)> {
+        for tag in ["<br>", "<br/>", "<br />"] {
+            if rest.starts_with(tag) {
+                return Some((Inline::LineBreak, tag.len()));
+            }
+        }
+
+        for (tag, style) in [
+            ("strong", EmphasisStyle::Bold),
+            ("b", EmphasisStyle::Bold),
+            ("em", EmphasisStyle::Italic),
+            ("i", EmphasisStyle::Italic),
+            ("del", EmphasisStyle::Strike),
+            ("s", EmphasisStyle::Strike),
+        ] {
+            let open = format!("<{tag}>");
+            let close = format!("</{tag}>");
+            if let Some(after) = rest.strip_prefix(&open) {
+                let end = after.find(&close)?;
+                let children = self.parse_inline(&after[..end]);
+                let len = open.len() + end + close.len();
+                return Some((Inline::Emphasis { style, children }, len));
+            }
+        }
+
+        let end = rest.find('>')?;
+        let url = &rest[1..end];
+        if is_autolink(url) {
+            let link = Inline::Link {
+                text: url.to_string(),
+                target: LinkTarget::Url(url.to_string()),
+            };
+            return Some((link, end + 1));
+        }
+        None
+    }
+
+    // `*` and `_` runs of one to three characters and `~~`. A run closes the
+    // span when it has the same length, or three characters that also close
+    // a nested span (`**a *b***`).
+    fn emphasis(&self, text: &str, pos: usize) -> Option<(Inline, usize)> {
+        let rest = &text[pos..];
+        let marker = rest.chars().next()?;
+        let run = rest.chars().take_while(|c| *c == marker).count();
+        if (marker == '~' && run != 2) || run > 3 {
+            return None;
+        }
+
+        let prev = text[..pos].chars().last();
+        let next = rest[run..].chars().next();
+        if next.is_none_or(char::is_whitespace)
+            || (marker == '_' && prev.is_some_and(char::is_alphanumeric))
+        {
+            return None;
+        }
+
+        let inner_start = pos + run;
+        let close = find_closing_run(text, inner_start, marker, run)?;
+        let children = self.parse_inline(&text[inner_start..close]);
+        let inline = match (marker, run) {
+            ('~', _) => Inline::Emphasis {
+                style: EmphasisStyle::Strike,
+                children,
+            },
+            (_, 1) => Inline::Emphasis {
+                style: EmphasisStyle::Italic,
+                children,
+            },
+            (_, 2) => Inline::Emphasis {
+                style: EmphasisStyle::Bold,
+                children,
+            },
+            _ => Inline::Emphasis {
+                style: EmphasisStyle::Bold,
+                children: vec![Inline::Emphasis {
+                    style: EmphasisStyle::Italic,
+                    children,
+                }],
+            },
+        };
+        Some((inline, close + run - pos))
+    }
+
+    // Links into the space are turned back into their Backlog forms: issue
+    // links become the bare key and wiki URLs become page links.
+    fn link(&self, text: String, url: &str) -> Inline {
+        if self.is_issue_link(&text, url) {
+            return Inline::Text(text);
+        }
+        if let Some(page) = self.wiki_page(url) {
+            return Inline::Link {
+                text,
+                target: LinkTarget::WikiPage(page),
+            };
+        }
+        Inline::Link {
+            text,
+            target: LinkTarget::Url(decode_link_url(url)),
+        }
+    }
+
+    fn is_issue_link(&self, text: &str, url: &str) -> bool {
+        let Some((project_key, _)) = text.rsplit_once('-') else {
+            return false;
+        };
+        if !self.options.project_keys.iter().any(|k| k == project_key) {
+            return false;
+        }
+        let web_url = self
+            .options
+            .space_url
+            .as_deref()
+            .map(|space| format!("{}/view/{text}", space.trim_end_matches('/')));
+        url == format!("{text}.md") || Some(url) == web_url.as_deref()
+    }
+
+    fn attachment_name(&self, url: &str) -> Option<String> {
+        let dir = self.options.asset_dir.as_deref()?;
+        let name = url
+            .strip_prefix(dir.trim_end_matches('/'))?
+            .strip_prefix('/')?;
+        Some(decode_link_url(name))
+    }
+
+    fn wiki_page(&self, url: &str) -> Option<String> {
+        let space_url = self.options.space_url.as_deref()?;
+        let project_key = self.options.project_key.as_deref()?;
+        let prefix = format!(
+            "{}/wiki/{}/",
+            space_url.trim_end_matches('/'),
+            urlencoding::encode(project_key)
+        );
+        let page = url.strip_prefix(&prefix)?;
+        urlencoding::decode(page).ok().map(|page| page.into_owned())
+    }
+}
+
+fn parse_fence(lines: &[&str]) -> Option<(Block, usize)> {
+    let trimmed = lines[0].trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence_len = trimmed.chars().take_while(|c| *c == marker).count();
+    let info = trimmed[fence_len..].trim();
+    if fence_len < 3 || (marker == '`' && info.contains('`')) {
+        return None;
+    }
+
+    let language = info.split_whitespace().next().map(str::to_string);
+    let mut code = Vec::new();
+    for (i, line) in lines[1..].iter().enumerate() {
+        let closing = line.trim();
+        if closing.len() >= fence_len && closing.chars().all(|c| c == marker) {
+            let block = Block::Code {
+                language,
+                lines: code,
+            };
+            return Some((block, i + 2));
+        }
+        code.push(line.to_string());
+    }
+
+    // As in CommonMark, an unclosed fence runs to the end of the document.
+    let block = Block::Code {
+        language,
+        lines: code,
+    };
+    Some((block, lines.len()))
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let trimmed = line.trim_start();
+    let level = trimmed.bytes().take_while(|b| *b == b'#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    // An optional closing `#` sequence must be separated by a space (`C#` stays).
+    let content = rest.trim();
+    let without_closing = content.trim_end_matches('#');
+    if without_closing.is_empty() || without_closing.ends_with([' ', '\t']) {
+        Some((level, without_closing.trim_end()))
+    } else {
+        Some((level, content))
+    }
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let compact: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|marker| compact.chars().all(|c| c == *marker))
+}
+
+struct ListMarker<'a> {
+    indent: usize,
+    content_column: usize,
+    ordered: bool,
+    content: &'a str,
+}
+
+// `-`, `*`, `+` bullets and `1.` / `1)` numbered items.
+fn list_marker(line: &str) -> Option<ListMarker<'_>> {
+    let rest = line.trim_start_matches([' ', '\t']);
+    let indent = line.len() - rest.len();
+    let (marker_len, ordered) = if rest.starts_with(['-', '*', '+']) {
+        (1, false)
+    } else {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        if !(1..=9).contains(&digits) || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        (digits + 1, true)
+    };
+
+    let after = &rest[marker_len..];
+    let content = after.trim_start_matches([' ', '\t']);
+    if content.len() == after.len() || content.is_empty() {
+        return None;
+    }
+    Some(ListMarker {
+        indent,
+        content_column: indent + marker_len + 1,
+        ordered,
+        content,
+    })
+}
+
+fn is_delimiter_row(line: &str) -> bool {
+    let cells = split_table_row(line);
+    line.trim_start().starts_with('|')
+        && cells.iter().all(|cell| {
+            let cell = cell.trim();
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+// Splits on `|` that is not escaped as `\|`, which GFM requires even inside
+// code spans.
+fn split_table_row(line: &str) -> Vec<&str> {
+    let body = line.trim();
+    let body = body.strip_prefix('|').unwrap_or(body);
+    let body = match body.strip_suffix('|') {
+        Some(inner) if !inner.ends_with('\\') => inner,
+        _ => body,
+    };
+
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        if c == '|' && !escaped {
+            cells.push(&body[start..i]);
+            start = i + 1;
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells.push(&body[start..]);
+    cells
+}
+
+fn code_span(rest: &str) -> (Inline, usize) {
+    let fence = rest.chars().take_while(|c| *c == '`').count();
+    let mut search = fence;
+    while let Some(offset) = rest[search..].find('`') {
+        let start = search + offset;
+        let run = rest[start..].chars().take_while(|c| *c == '`').count();
+        if run == fence {
+            let code = &rest[fence..start];
+            let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                Some(inner) if !inner.trim().is_empty() => inner,
+                _ => code,
+            };
+            return (Inline::Code(code.to_string()), start + run);
+        }
+        search = start + run;
+    }
+    // An unmatched run is literal as a whole, not the start of a shorter span.
+    (Inline::Text(rest[..fence].to_string()), fence)
+}
+
+// Splits `[label](url)` into its parts and total length. Brackets in the
+// label must be balanced or escaped.
+fn link_parts(rest: &str) -> Option<(&str, &str, usize)> {
+    let label_end = matching_bracket(rest, '[', ']')?;
+    let after = &rest[label_end + 1..];
+    if !after.starts_with('(') {
+        return None;
+    }
+    let url_end = matching_bracket(after, '(', ')')?;
+    let url = after[1..url_end].trim();
+    let url = url
+        .strip_prefix('<')
+        .and_then(|u| u.strip_suffix('>'))
+        .unwrap_or(url);
+    Some((&rest[1..label_end], url, label_end + 1 + url_end + 1))
+}
+
+fn matching_bracket(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
+// Finds where the span opened by a `marker` run of `run` characters closes,
+// skipping code spans and escapes.
+fn find_closing_run(text: &str, start: usize, marker: char, run: usize) -> Option<usize> {
+    let mut pos = start;
+    while let Some(c) = text[pos..].chars().next() {
+        if c == '\\' {
+            pos += 1 + text[pos + 1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if c == '`' {
+            pos += code_span(&text[pos..]).1;
+            continue;
+        }
+        if c != marker {
+            pos += c.len_utf8();
+            continue;
+        }
+
+        let len = text[pos..].chars().take_while(|c| *c == marker).count();
+        let prev = text[..pos].chars().last();
+        let next = text[pos + len..].chars().next();
+        let flanking = prev.is_some_and(|p| !p.is_whitespace())
+            && !(marker == '_' && next.is_some_and(char::is_alphanumeric));
+        if flanking && pos > start && (len == run || (len == 3 && run < 3)) {
+            return Some(pos + len - run);
+        }
+        pos += len;
+    }
+    None
+}
+
+fn is_autolink(url: &str) -> bool {
+    !url.contains([' ', '<'])
+        && ["http://", "https://", "ftp://", "mailto:"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme))
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(next) if c == '\\' && next.is_ascii_punctuation() => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Reverses the escaping of characters that would end a Markdown link URL.
+fn decode_link_url(url: &str) -> String {
+    url.replace("%20", " ")
+        .replace("%28", "(")
+        .replace("%29", ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::markdown::ast::{Block, EmphasisStyle, Inline, List, ListItem};
+    use crate::markdown::ConvertOptions;
+
+    fn parse_line(line: &str) -> Vec<Inline> {
+        match parse(line, &ConvertOptions::default()).blocks.remove(0) {
+            Block::Line(inlines) => inlines,
+            other => panic!("expected a line, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn list_depth_follows_indentation() {
+        let doc = parse("- a\n  1. b\n- c", &ConvertOptions::default());
+        let item = |text: &str, children: Vec<List>| ListItem {
+            lines: vec![vec![Inline::Text(text.to_string())]],
+            children,
+        };
+        assert_eq!(
+            doc.blocks,
+            vec![Block::List(List {
+                ordered: false,
+                items: vec![
+                    item(
+                        "a",
+                        vec![List {
+                            ordered: true,
+                            items: vec![item("b", Vec::new())],
+                        }]
+                    ),
+                    item("c", Vec::new()),
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn intraword_underscores_are_not_emphasis() {
+        assert_eq!(
+            parse_line("snake_case_name and _em_"),
+            vec![
+                Inline::Text("snake_case_name and ".to_string()),
+                Inline::Emphasis {
+                    style: EmphasisStyle::Italic,
+                    children: vec![Inline::Text("em".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_closers_skip_nested_runs() {
+        let text = |t: &str| Inline::Text(t.to_string());
+        assert_eq!(
+            parse_line("*a **b** c*"),
+            vec![Inline::Emphasis {
+                style: EmphasisStyle::Italic,
+                children: vec![
+                    text("a "),
+                    Inline::Emphasis {
+                        style: EmphasisStyle::Bold,
+                        children: vec![text("b")],
+                    },
+                    text(" c"),
+                ],
+            }]
+        );
+        assert_eq!(parse_line("2 * 3 * 4"), vec![text("2 * 3 * 4")]);
+    }
+}
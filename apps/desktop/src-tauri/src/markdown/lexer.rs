@@ -0,0 +1,157 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Text,
+    CodeOpen,
+    CodeClose,
+    LinkOpen,
+    LinkClose,
+    ColorOpen,
+    ColorClose,
+    LineBreak,
+    Bold,
+    Italic,
+    BoldItalic,
+    Strike,
+    Image,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+const COLOR_OPEN_PREFIX: &str = "{color:";
+const COLOR_CLOSE: &str = "{color}";
+const LINE_BREAK: &str = "&br;";
+const IMAGE_MACROS: [&str; 2] = ["#image(", "#thumbnail("];
+
+// Splits a single line into inline tokens. Every byte of the input ends up in
+// exactly one token, so callers can always reconstruct the source verbatim.
+pub fn tokenize_inline(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    while pos < line.len() {
+        let rest = &line[pos..];
+        let matched = match_marker(rest);
+
+        match matched {
+            Some((kind, len)) => {
+                if text_start < pos {
+                    tokens.push(Token {
+                        kind: TokenKind::Text,
+                        text: &line[text_start..pos],
+                    });
+                }
+                tokens.push(Token {
+                    kind,
+                    text: &line[pos..pos + len],
+                });
+                pos += len;
+                text_start = pos;
+            }
+            None => {
+                pos += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+            }
+        }
+    }
+
+    if text_start < line.len() {
+        tokens.push(Token {
+            kind: TokenKind::Text,
+            text: &line[text_start..],
+        });
+    }
+
+    tokens
+}
+
+fn match_marker(rest: &str) -> Option<(TokenKind, usize)> {
+    if rest.starts_with('\'') {
+        return match_quote_run(rest);
+    }
+    if rest.starts_with("%%") {
+        return Some((TokenKind::Strike, 2));
+    }
+    if rest.starts_with("{{") {
+        return Some((TokenKind::CodeOpen, 2));
+    }
+    if rest.starts_with("}}") {
+        return Some((TokenKind::CodeClose, 2));
+    }
+    if rest.starts_with("[[") {
+        return Some((TokenKind::LinkOpen, 2));
+    }
+    if rest.starts_with("]]") {
+        return Some((TokenKind::LinkClose, 2));
+    }
+    if rest.starts_with(COLOR_CLOSE) {
+        return Some((TokenKind::ColorClose, COLOR_CLOSE.len()));
+    }
+    if rest.starts_with(LINE_BREAK) {
+        return Some((TokenKind::LineBreak, LINE_BREAK.len()));
+    }
+    if rest.starts_with(COLOR_OPEN_PREFIX) {
+        let end = rest.find('}')?;
+        return Some((TokenKind::ColorOpen, end + 1));
+    }
+    if IMAGE_MACROS.iter().any(|prefix| rest.starts_with(prefix)) {
+        let end = rest.find(')')?;
+        return Some((TokenKind::Image, end + 1));
+    }
+    None
+}
+
+// `''` is bold, `'''` italic and `'''''` both. Runs of four or six are two
+// adjacent markers (e.g. `''a''''b''`); longer runs are plain text.
+fn match_quote_run(rest: &str) -> Option<(TokenKind, usize)> {
+    let run = rest.bytes().take_while(|b| *b == b'\'').count();
+    match run {
+        1 => None,
+        2 | 4 => Some((TokenKind::Bold, 2)),
+        3 | 6 => Some((TokenKind::Italic, 3)),
+        5 => Some((TokenKind::BoldItalic, 5)),
+        _ => Some((TokenKind::Text, run)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize_inline, TokenKind};
+
+    #[test]
+    fn tokens_cover_the_whole_line() {
+        let line = "a {{b}} [[c]] {color:red}d{color} 日本語";
+        let tokens = tokenize_inline(line);
+        let rebuilt: String = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(rebuilt, line);
+    }
+
+    #[test]
+    fn color_open_without_brace_is_text() {
+        let tokens = tokenize_inline("{color:red and more");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::Text);
+    }
+
+    #[test]
+    fn quote_runs_split_into_markers() {
+        let kinds: Vec<TokenKind> = tokenize_inline("''a''''b'''''''")
+            .into_iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Bold,
+                TokenKind::Text,
+                TokenKind::Bold,
+                TokenKind::Bold,
+                TokenKind::Text,
+                TokenKind::Text,
+            ]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use super::ast::{Block, Document, EmphasisStyle, Inline, LinkTarget, List, TableRow};
+
+// Renders a document as Backlog notation, in the forms `parser` reads back.
+pub fn render_document(document: &Document) -> String {
+    let mut lines = Vec::new();
+    render_blocks(&document.blocks, &mut lines);
+    lines.join("\n")
+}
+
+fn render_blocks(blocks: &[Block], lines: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            Block::Heading { level, content } => {
+                lines.push(format!("h{level}. {}", render_inlines(content)));
+            }
+            Block::List(list) => render_list(list, 1, lines),
+            Block::Quote(children) => {
+                lines.push("{quote}".to_string());
+                render_blocks(children, lines);
+                lines.push("{/quote}".to_string());
+            }
+            Block::Code {
+                language,
+                lines: code,
+            } => {
+                lines.push(match language {
+                    Some(language) => format!("{{code:{language}}}"),
+                    None => "{code}".to_string(),
+                });
+                lines.extend(code.iter().cloned());
+                lines.push("{/code}".to_string());
+            }
+            Block::Table(rows) => lines.extend(rows.iter().map(render_table_row)),
+            Block::Line(content) => lines.push(render_inlines(content)),
+            Block::Blank => lines.push(String::new()),
+        }
+    }
+}
+
+// The marker is repeated once per level (`**`, `++`); continuation lines are
+// indented past it.
+fn render_list(list: &List, depth: usize, lines: &mut Vec<String>) {
+    let marker = if list.ordered { "+" } else { "*" }.repeat(depth);
+    let indent = " ".repeat(depth + 1);
+    for item in &list.items {
+        for (i, line) in item.lines.iter().enumerate() {
+            let prefix = if i == 0 {
+                format!("{marker} ")
+            } else {
+                indent.clone()
+            };
+            lines.push(format!("{prefix}{}", render_inlines(line)));
+        }
+        for child in &item.children {
+            render_list(child, depth + 1, lines);
+        }
+    }
+}
+
+fn render_table_row(row: &TableRow) -> String {
+    let cells: Vec<String> = row
+        .cells
+        .iter()
+        .map(|cell| render_inlines(&escape_cell_pipes(cell)))
+        .collect();
+    let suffix = if row.header { "h" } else { "" };
+    format!("|{}|{suffix}", cells.join("|"))
+}
+
+// A `|` in cell text would start a new cell; Backlog shows the character
+// reference as a literal pipe.
+fn escape_cell_pipes(inlines: &[Inline]) -> Vec<Inline> {
+    let escape = |text: &str| text.replace('|', "&#124;");
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text) => Inline::Text(escape(text)),
+            Inline::Link { text, target } => Inline::Link {
+                text: escape(text),
+                target: target.clone(),
+            },
+            Inline::Color(children) => Inline::Color(escape_cell_pipes(children)),
+            Inline::Emphasis { style, children } => Inline::Emphasis {
+                style: *style,
+                children: escape_cell_pipes(children),
+            },
+            other => other.clone(),
+        })
+        .collect()
+}
+
+fn render_inlines(inlines: &[Inline]) -> String {
+    let mut out = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => out.push_str(text),
+            Inline::Code(code) => out.push_str(&format!("{{{{{code}}}}}")),
+            Inline::Link { text, target } => out.push_str(&render_link(text, target)),
+            Inline::Color(children) => out.push_str(&render_inlines(children)),
+            Inline::Image { name, thumbnail } => {
+                let kind = if *thumbnail { "thumbnail" } else { "image" };
+                out.push_str(&format!("#{kind}({name})"));
+            }
+            Inline::Emphasis { style, children } => {
+                let marker = match style {
+                    EmphasisStyle::Bold => "''",
+                    EmphasisStyle::Italic => "'''",
+                    EmphasisStyle::Strike => "%%",
+                };
+                out.push_str(&format!("{marker}{}{marker}", render_inlines(children)));
+            }
+            Inline::LineBreak => out.push_str("&br;"),
+        }
+    }
+    out
+}
+
+fn render_link(text: &str, target: &LinkTarget) -> String {
+    let target = match target {
+        LinkTarget::Url(value) | LinkTarget::WikiPage(value) => value,
+    };
+    if text == target {
+        format!("[[{target}]]")
+    } else {
+        format!("[[{text}>{target}]]")
+    }
+}
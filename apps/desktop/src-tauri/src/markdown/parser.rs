@@ -0,0 +1,849 @@
+use super::ast::{Block, Document, EmphasisStyle, Inline, LinkTarget, List, ListItem, TableRow};
+use super::lexer::{tokenize_inline, Token, TokenKind};
+use super::ConvertOptions;
+use crate::models::ConversionWarning;
+
+const QUOTE_OPEN: &str = "{quote}";
+const QUOTE_CLOSE: &str = "{/quote}";
+const CODE_OPEN_PREFIX: &str = "{code";
+const CODE_CLOSE: &str = "{/code}";
+
+// Backlog macros that have no Markdown counterpart and stay in the text as typed.
+const UNSUPPORTED_MACROS: [&str; 4] = ["#attach(", "#contents", "#rev(", "&color("];
+
+pub fn parse(input: &str, options: &ConvertOptions) -> Document {
+    let normalized = input.replace("\r\n", "\n");
+    let lines: Vec<&str> = normalized.split('\n').collect();
+    let mut parser = BlockParser {
+        lines,
+        pos: 0,
+        first_line: 1,
+        links_wiki_pages: options.space_url.is_some() && options.project_key.is_some(),
+        warnings: Vec::new(),
+    };
+    let blocks = parser.parse_blocks(None);
+    let mut warnings = parser.warnings;
+    warnings.sort_by_key(|w| w.line);
+    Document { blocks, warnings }
+}
+
+struct BlockParser<'a> {
+    lines: Vec<&'a str>,
+    pos: usize,
+    // Line number of `lines[0]` in the input; `>` quotes are parsed separately.
+    first_line: usize,
+    links_wiki_pages: bool,
+    warnings: Vec<ConversionWarning>,
+}
+
+impl<'a> BlockParser<'a> {
+    // `quote_start` is the index of the `{quote}` line when inside a quote region.
+    fn parse_blocks(&mut self, quote_start: Option<usize>) -> Vec<Block> {
+        let mut blocks = Vec::new();
+
+        while self.pos < self.lines.len() {
+            let line = self.lines[self.pos];
+            let trimmed = line.trim();
+
+            if quote_start.is_some()
+                && (trimmed == QUOTE_CLOSE || self.is_legacy_quote_close(trimmed))
+            {
+                self.pos += 1;
+                return blocks;
+            }
+
+            if trimmed == QUOTE_OPEN {
+                self.pos += 1;
+                blocks.push(Block::Quote(self.parse_blocks(Some(self.pos - 1))));
+                continue;
+            }
+
+            if line.starts_with('>') {
+                blocks.push(self.parse_line_quote());
+                continue;
+            }
+
+            if let Some(blocks_from_code) = self.parse_code_block() {
+                blocks.extend(blocks_from_code);
+                continue;
+            }
+
+            if list_marker(line).is_some() {
+                blocks.extend(self.parse_lists().into_iter().map(Block::List));
+                continue;
+            }
+
+            if is_table_row_start(line) {
+                if let Some(table) = self.parse_table() {
+                    blocks.push(table);
+                    continue;
+                }
+            }
+
+            self.pos += 1;
+            blocks.push(self.parse_line(line, self.pos - 1));
+        }
+
+        if let Some(start) = quote_start {
+            self.warn(start, QUOTE_OPEN, "unclosed quote closed at end of text");
+        }
+        blocks
+    }
+
+    // Consecutive `>` lines form one quote. One `>` and the space after it are
+    // stripped and the rest is parsed again, so `>>` nests and `> * a` is a list.
+    fn parse_line_quote(&mut self) -> Block {
+        let start = self.pos;
+        let mut inner = Vec::new();
+        while let Some(rest) = self.lines.get(self.pos).and_then(|l| l.strip_prefix('>')) {
+            inner.push(rest.strip_prefix(' ').unwrap_or(rest));
+            self.pos += 1;
+        }
+
+        let mut parser = BlockParser {
+            lines: inner,
+            pos: 0,
+            first_line: self.line_number(start),
+            links_wiki_pages: self.links_wiki_pages,
+            warnings: Vec::new(),
+        };
+        let blocks = parser.parse_blocks(None);
+        self.warnings.append(&mut parser.warnings);
+        Block::Quote(blocks)
+    }
+
+    // Everything between `{code}` and `{/code}` is kept verbatim. Text after the
+    // closing marker on the same line is parsed as an ordinary line. An unclosed
+    // `{code}` is left as text rather than swallowing the rest of the document.
+    fn parse_code_block(&mut self) -> Option<Vec<Block>> {
+        let (language, first) = code_open(self.lines[self.pos])?;
+
+        let mut lines = Vec::new();
+        let mut candidate = first;
+        let mut offset = 0;
+        loop {
+            if let Some(end) = candidate.find(CODE_CLOSE) {
+                let before = &candidate[..end];
+                if !before.trim().is_empty() {
+                    lines.push(before.to_string());
+                }
+                let after = &candidate[end + CODE_CLOSE.len()..];
+                self.pos += offset + 1;
+
+                let mut blocks = vec![Block::Code { language, lines }];
+                if !after.trim().is_empty() {
+                    blocks.push(self.parse_line(after.trim_start(), self.pos - 1));
+                }
+                return Some(blocks);
+            }
+
+            if offset > 0 || !candidate.trim().is_empty() {
+                lines.push(candidate.to_string());
+            }
+            offset += 1;
+            match self.lines.get(self.pos + offset) {
+                Some(next) => candidate = next,
+                None => {
+                    self.warn(self.pos, "{code}", "unclosed code block kept as text");
+                    return None;
+                }
+            }
+        }
+    }
+
+    // Collects consecutive list lines. An indented line directly below an item
+    // that is not itself a list line continues that item.
+    fn parse_lists(&mut self) -> Vec<List> {
+        let mut entries: Vec<ListEntry> = Vec::new();
+        while self.pos < self.lines.len() {
+            let line = self.lines[self.pos];
+            if let Some(marker) = list_marker(line) {
+                let content = self.parse_inline(marker.content, self.pos);
+                entries.push(ListEntry {
+                    depth: marker.depth,
+                    ordered: marker.ordered,
+                    lines: vec![content],
+                });
+            } else if !entries.is_empty() && is_continuation(line) {
+                let content = self.parse_inline(line.trim(), self.pos);
+                if let Some(entry) = entries.last_mut() {
+                    entry.lines.push(content);
+                }
+            } else {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        // A level may only be one deeper than the previous line, so `*` followed
+        // by `***` nests the second item directly under the first.
+        let mut prev_depth = 0;
+        for entry in &mut entries {
+            entry.depth = entry.depth.min(prev_depth + 1);
+            prev_depth = entry.depth;
+        }
+
+        build_lists(entries, 1)
+    }
+
+    fn parse_table(&mut self) -> Option<Block> {
+        let mut rows = Vec::new();
+        while self.pos < self.lines.len() && is_table_row_start(self.lines[self.pos]) {
+            let Some((source, consumed)) = self.collect_table_row() else {
+                break;
+            };
+            let row = self.parse_table_row(&source, self.pos);
+            if row.header && !rows.is_empty() {
+                self.warn(
+                    self.pos,
+                    "|h",
+                    "header row below the first row rendered as a body row",
+                );
+            }
+            rows.push(row);
+            self.pos += consumed;
+        }
+
+        if rows.is_empty() {
+            None
+        } else {
+            Some(Block::Table(rows))
+        }
+    }
+
+    // A row normally fits on one line. When a cell contains a raw line break
+    // the row is continued on the following lines until one closes it with `|`;
+    // the breaks are kept as `&br;` so they survive as in-cell line breaks.
+    fn collect_table_row(&self) -> Option<(String, usize)> {
+        let first = self.lines[self.pos].trim();
+        if closes_table_row(first) {
+            return Some((first.to_string(), 1));
+        }
+
+        let mut source = first.to_string();
+        for (offset, line) in self.lines[self.pos + 1..].iter().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('|') {
+                return None;
+            }
+            source.push_str("&br;");
+            source.push_str(trimmed);
+            if closes_table_row(trimmed) {
+                return Some((source, offset + 2));
+            }
+        }
+        None
+    }
+
+    // Older descriptions use `{quote}` both to open and to close a region.
+    // Inside a quote, a bare `{quote}` closes it unless a `{/quote}` follows.
+    fn is_legacy_quote_close(&self, trimmed: &str) -> bool {
+        trimmed == QUOTE_OPEN
+            && !self.lines[self.pos + 1..]
+                .iter()
+                .any(|l| l.trim() == QUOTE_CLOSE)
+    }
+
+    fn parse_line(&mut self, line: &str, index: usize) -> Block {
+        if line.trim().is_empty() {
+            return Block::Blank;
+        }
+
+        if let Some((level, rest)) = heading_prefix(line) {
+            return Block::Heading {
+                level,
+                content: self.parse_inline(rest, index),
+            };
+        }
+
+        Block::Line(self.parse_inline(line, index))
+    }
+
+    fn parse_table_row(&mut self, source: &str, index: usize) -> TableRow {
+        let (body, header) = match source.strip_suffix('h') {
+            Some(body) if body.ends_with('|') => (body, true),
+            _ => (source, false),
+        };
+        let body = body.strip_prefix('|').unwrap_or(body);
+        let body = body.strip_suffix('|').unwrap_or(body);
+
+        TableRow {
+            header,
+            cells: split_table_cells(body)
+                .into_iter()
+                .map(|cell| self.parse_inline(cell.trim(), index))
+                .collect(),
+        }
+    }
+
+    fn parse_inline(&mut self, text: &str, index: usize) -> Vec<Inline> {
+        let mut sink = WarningSink {
+            line: self.line_number(index),
+            links_wiki_pages: self.links_wiki_pages,
+            warnings: &mut self.warnings,
+        };
+        parse_inline(text, &mut sink)
+    }
+
+    fn line_number(&self, index: usize) -> usize {
+        self.first_line + index
+    }
+
+    fn warn(&mut self, index: usize, construct: &str, action: &str) {
+        let line = self.line_number(index);
+        self.warnings.push(warning(line, construct, action));
+    }
+}
+
+// Collects warnings for the inlines of one source line.
+struct WarningSink<'w> {
+    line: usize,
+    links_wiki_pages: bool,
+    warnings: &'w mut Vec<ConversionWarning>,
+}
+
+impl WarningSink<'_> {
+    fn warn(&mut self, construct: &str, action: &str) {
+        self.warnings.push(warning(self.line, construct, action));
+    }
+
+    fn check_macros(&mut self, text: &str) {
+        for name in UNSUPPORTED_MACROS {
+            for _ in text.matches(name) {
+                self.warn(name.trim_end_matches('('), "kept as plain text");
+            }
+        }
+    }
+}
+
+fn warning(line: usize, construct: &str, action: &str) -> ConversionWarning {
+    ConversionWarning {
+        construct: construct.to_string(),
+        line,
+        action: action.to_string(),
+    }
+}
+
+fn code_open(line: &str) -> Option<(Option<String>, &str)> {
+    let rest = line.trim_start().strip_prefix(CODE_OPEN_PREFIX)?;
+    let (language, rest) = if let Some(rest) = rest.strip_prefix('}') {
+        (None, rest)
+    } else {
+        let rest = rest.strip_prefix(':')?;
+        let end = rest.find('}')?;
+        (code_language(&rest[..end]), &rest[end + 1..])
+    };
+    Some((language, rest))
+}
+
+// Keeps the info string to a single word so it cannot break the fence line.
+fn code_language(raw: &str) -> Option<String> {
+    raw.split_whitespace()
+        .next()
+        .map(|word| word.replace('`', ""))
+        .filter(|word| !word.is_empty())
+}
+
+fn is_table_row_start(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn closes_table_row(line: &str) -> bool {
+    line.len() >= 2 && (line.ends_with('|') || line.ends_with("|h"))
+}
+
+// Splits on `|`, except inside `{{...}}` and `[[...]]` where a pipe is content.
+fn split_table_cells(body: &str) -> Vec<&str> {
+    let mut cells = Vec::new();
+    let mut cell_start = 0;
+    let mut pos = 0;
+
+    while pos < body.len() {
+        let rest = &body[pos..];
+        let skip_to = [("{{", "}}"), ("[[", "]]")]
+            .iter()
+            .find(|(open, _)| rest.starts_with(open))
+            .and_then(|(open, close)| {
+                rest[open.len()..]
+                    .find(close)
+                    .map(|i| i + open.len() + close.len())
+            });
+
+        if let Some(len) = skip_to {
+            pos += len;
+        } else if rest.starts_with('|') {
+            cells.push(&body[cell_start..pos]);
+            pos += 1;
+            cell_start = pos;
+        } else {
+            pos += rest.chars().next().map(char::len_utf8).unwrap_or(1);
+        }
+    }
+
+    cells.push(&body[cell_start..]);
+    cells
+}
+
+fn heading_prefix(line: &str) -> Option<(usize, &str)> {
+    let bytes = line.as_bytes();
+    if bytes.len() < 4 || bytes[0] != b'h' || bytes[2] != b'.' {
+        return None;
+    }
+    let level = match bytes[1] {
+        b @ b'1'..=b'6' => usize::from(b - b'0'),
+        _ => return None,
+    };
+    let rest = &line[3..];
+    let content = rest.trim_start_matches([' ', '\t']);
+    if content.len() == rest.len() {
+        return None;
+    }
+    Some((level, content))
+}
+
+struct ListMarker<'a> {
+    depth: usize,
+    ordered: bool,
+    content: &'a str,
+}
+
+pub(super) struct ListEntry {
+    pub depth: usize,
+    pub ordered: bool,
+    pub lines: Vec<Vec<Inline>>,
+}
+
+// `*`, `**`, `***` are bullets and `+`, `++` numbered items; the marker run
+// must be followed by whitespace so that `+1` or `*emphasis*` stay text.
+fn list_marker(line: &str) -> Option<ListMarker<'_>> {
+    let marker = line.chars().next().filter(|c| *c == '*' || *c == '+')?;
+    let depth = line.chars().take_while(|c| *c == marker).count();
+    let rest = &line[depth..];
+    let content = rest.trim_start_matches([' ', '\t']);
+    if content.len() == rest.len() {
+        return None;
+    }
+    Some(ListMarker {
+        depth,
+        ordered: marker == '+',
+        content,
+    })
+}
+
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t']) && !line.trim().is_empty()
+}
+
+pub(super) fn build_lists(entries: Vec<ListEntry>, depth: usize) -> Vec<List> {
+    let mut lists: Vec<List> = Vec::new();
+    let mut nested: Vec<ListEntry> = Vec::new();
+
+    for entry in entries {
+        if entry.depth > depth {
+            nested.push(entry);
+            continue;
+        }
+        attach_children(&mut lists, std::mem::take(&mut nested), depth);
+
+        match lists.last_mut() {
+            Some(list) if list.ordered == entry.ordered => {}
+            _ => lists.push(List {
+                ordered: entry.ordered,
+                items: Vec::new(),
+            }),
+        }
+        if let Some(list) = lists.last_mut() {
+            list.items.push(ListItem {
+                lines: entry.lines,
+                children: Vec::new(),
+            });
+        }
+    }
+    attach_children(&mut lists, nested, depth);
+
+    lists
+}
+
+fn attach_children(lists: &mut [List], nested: Vec<ListEntry>, depth: usize) {
+    if nested.is_empty() {
+        return;
+    }
+    if let Some(item) = lists.last_mut().and_then(|l| l.items.last_mut()) {
+        item.children = build_lists(nested, depth + 1);
+    }
+}
+
+fn parse_inline(line: &str, sink: &mut WarningSink<'_>) -> Vec<Inline> {
+    let tokens = tokenize_inline(line);
+    let mut pos = 0;
+    let mut out = Vec::new();
+    parse_inline_until(&tokens, &mut pos, &[], &mut out, sink);
+    out
+}
+
+// Parses inlines until a token in `stops` is reached. The stop token is left
+// unconsumed so that the caller which opened the span can tell whether it was
+// closed by its own marker or by the marker of an enclosing span.
+fn parse_inline_until(
+    tokens: &[Token<'_>],
+    pos: &mut usize,
+    stops: &[TokenKind],
+    out: &mut Vec<Inline>,
+    sink: &mut WarningSink<'_>,
+) {
+    while *pos < tokens.len() {
+        let token = tokens[*pos];
+        if stops.contains(&token.kind) {
+            return;
+        }
+        *pos += 1;
+
+        match token.kind {
+            TokenKind::Text | TokenKind::CodeClose | TokenKind::LinkClose => {
+                sink.check_macros(token.text);
+                push_text(out, token.text);
+            }
+            TokenKind::CodeOpen => match raw_until(tokens, *pos, TokenKind::CodeClose) {
+                Some((raw, next)) => {
+                    out.push(Inline::Code(raw.trim().to_string()));
+                    *pos = next;
+                }
+                None => push_text(out, token.text),
+            },
+            TokenKind::LinkOpen => match raw_until(tokens, *pos, TokenKind::LinkClose) {
+                Some((raw, next)) => {
+                    let link = parse_link(&raw);
+                    if matches!(
+                        link,
+                        Inline::Link {
+                            target: LinkTarget::WikiPage(_),
+                            ..
+                        }
+                    ) && !sink.links_wiki_pages
+                    {
+                        sink.warn("[[wiki link]]", "kept page name as plain text");
+                    }
+                    out.push(link);
+                    *pos = next;
+                }
+                None => push_text(out, token.text),
+            },
+            TokenKind::ColorOpen => {
+                sink.warn("{color}", "color removed, text kept");
+                let children = parse_span(tokens, pos, stops, TokenKind::ColorClose, sink);
+                // An unclosed color runs to the end of the enclosing span.
+                if closes_at(tokens, *pos, TokenKind::ColorClose) {
+                    *pos += 1;
+                }
+                out.push(Inline::Color(children));
+            }
+            TokenKind::Bold | TokenKind::Italic | TokenKind::BoldItalic | TokenKind::Strike => {
+                let children = parse_span(tokens, pos, stops, token.kind, sink);
+                if closes_at(tokens, *pos, token.kind) && !children.is_empty() {
+                    *pos += 1;
+                    push_emphasis(out, token.kind, children);
+                } else {
+                    push_text(out, token.text);
+                    append_inlines(out, children);
+                }
+            }
+            TokenKind::LineBreak => out.push(Inline::LineBreak),
+            TokenKind::Image => out.push(parse_image(token.text)),
+            // A stray `{color}` carries no content of its own.
+            TokenKind::ColorClose => sink.warn("{color}", "stray closing marker removed"),
+        }
+    }
+}
+
+// `[[text>target]]` links to a URL or wiki page, `[[text:url]]` only to a URL
+// (so `[[Page:Sub]]` stays a page name) and `[[target]]` to itself.
+fn parse_link(raw: &str) -> Inline {
+    let (text, target) = match raw.split_once('>') {
+        Some((text, target)) => (text.trim(), target.trim()),
+        None => raw
+            .match_indices(':')
+            .map(|(i, _)| (raw[..i].trim(), raw[i + 1..].trim()))
+            .find(|(text, target)| !text.is_empty() && is_url(target))
+            .unwrap_or((raw.trim(), raw.trim())),
+    };
+
+    let target = if is_url(target) {
+        LinkTarget::Url(target.to_string())
+    } else {
+        LinkTarget::WikiPage(target.to_string())
+    };
+    let text = if text.is_empty() {
+        match &target {
+            LinkTarget::Url(value) | LinkTarget::WikiPage(value) => value.clone(),
+        }
+    } else {
+        text.to_string()
+    };
+
+    Inline::Link { text, target }
+}
+
+// `#image(name)` and `#thumbnail(name)` refer to an attachment of the issue.
+fn parse_image(source: &str) -> Inline {
+    let (kind, name) = source
+        .trim_end_matches(')')
+        .split_once('(')
+        .unwrap_or((source, ""));
+    Inline::Image {
+        name: name.trim().to_string(),
+        thumbnail: kind == "#thumbnail",
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    ["http://", "https://", "ftp://", "mailto:"]
+        .iter()
+        .any(|scheme| value.starts_with(scheme))
+}
+
+fn parse_span(
+    tokens: &[Token<'_>],
+    pos: &mut usize,
+    stops: &[TokenKind],
+    close: TokenKind,
+    sink: &mut WarningSink<'_>,
+) -> Vec<Inline> {
+    let mut nested_stops = stops.to_vec();
+    nested_stops.push(close);
+    let mut children = Vec::new();
+    parse_inline_until(tokens, pos, &nested_stops, &mut children, sink);
+    children
+}
+
+fn closes_at(tokens: &[Token<'_>], pos: usize, close: TokenKind) -> bool {
+    tokens.get(pos).is_some_and(|t| t.kind == close)
+}
+
+fn push_emphasis(out: &mut Vec<Inline>, kind: TokenKind, children: Vec<Inline>) {
+    let (style, children) = match kind {
+        TokenKind::Bold => (EmphasisStyle::Bold, children),
+        TokenKind::Italic => (EmphasisStyle::Italic, children),
+        TokenKind::Strike => (EmphasisStyle::Strike, children),
+        _ => (
+            EmphasisStyle::Bold,
+            vec![Inline::Emphasis {
+                style: EmphasisStyle::Italic,
+                children,
+            }],
+        ),
+    };
+
+    // Adjacent spans of the same style (`''a''''b''`) are merged, since
+    // `**a****b**` is not valid emphasis in Markdown.
+    if let Some(Inline::Emphasis {
+        style: prev_style,
+        children: prev_children,
+    }) = out.last_mut()
+    {
+        if *prev_style == style {
+            append_inlines(prev_children, children);
+            return;
+        }
+    }
+    out.push(Inline::Emphasis { style, children });
+}
+
+fn append_inlines(out: &mut Vec<Inline>, inlines: Vec<Inline>) {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => push_text(out, &text),
+            other => out.push(other),
+        }
+    }
+}
+
+// Returns the verbatim source between `start` and the next `close` token,
+// plus the index just past that token. Markers in between are not interpreted.
+fn raw_until(tokens: &[Token<'_>], start: usize, close: TokenKind) -> Option<(String, usize)> {
+    let end = tokens[start..].iter().position(|t| t.kind == close)? + start;
+    let raw = tokens[start..end].iter().map(|t| t.text).collect();
+    Some((raw, end + 1))
+}
+
+pub(super) fn push_text(out: &mut Vec<Inline>, text: &str) {
+    if let Some(Inline::Text(prev)) = out.last_mut() {
+        prev.push_str(text);
+    } else {
+        out.push(Inline::Text(text.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WarningSink;
+    use crate::markdown::ast::{
+        Block, Document, EmphasisStyle, Inline, LinkTarget, List, ListItem, TableRow,
+    };
+    use crate::markdown::ConvertOptions;
+
+    fn parse(input: &str) -> Document {
+        super::parse(input, &ConvertOptions::default())
+    }
+
+    fn parse_inline(line: &str) -> Vec<Inline> {
+        let mut warnings = Vec::new();
+        let mut sink = WarningSink {
+            line: 1,
+            links_wiki_pages: false,
+            warnings: &mut warnings,
+        };
+        super::parse_inline(line, &mut sink)
+    }
+
+    #[test]
+    fn code_span_contents_are_not_interpreted() {
+        let inlines = parse_inline("{{ [[a]] {color:red} }}");
+        assert_eq!(inlines, vec![Inline::Code("[[a]] {color:red}".to_string())]);
+    }
+
+    #[test]
+    fn quote_regions_nest_blocks() {
+        let doc = parse("{quote}\nh2. Title\n* item\n{/quote}\nafter");
+        assert_eq!(doc.blocks.len(), 2);
+        match &doc.blocks[0] {
+            Block::Quote(children) => assert_eq!(children.len(), 2),
+            other => panic!("expected quote, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn table_cells_keep_pipes_inside_code_and_links() {
+        let doc = parse("|{{a|b}}|[[x|y]]|h");
+        assert_eq!(
+            doc.blocks,
+            vec![Block::Table(vec![TableRow {
+                header: true,
+                cells: vec![
+                    vec![Inline::Code("a|b".to_string())],
+                    vec![Inline::Link {
+                        text: "x|y".to_string(),
+                        target: LinkTarget::WikiPage("x|y".to_string()),
+                    }],
+                ],
+            }])]
+        );
+    }
+
+    #[test]
+    fn code_blocks_keep_contents_verbatim() {
+        let doc = parse("{code:c}\n* ptr\n{/quote}\n{/code}");
+        assert_eq!(
+            doc.blocks,
+            vec![Block::Code {
+                language: Some("c".to_string()),
+                lines: vec!["* ptr".to_string(), "{/quote}".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn unmatched_emphasis_markers_stay_literal() {
+        assert_eq!(
+            parse_inline("''a %%b'' c"),
+            vec![
+                Inline::Emphasis {
+                    style: EmphasisStyle::Bold,
+                    children: vec![Inline::Text("a %%b".to_string())],
+                },
+                Inline::Text(" c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_levels_build_a_tree() {
+        let doc = parse("* a\n++ b\n* c");
+        let item = |text: &str, children: Vec<List>| ListItem {
+            lines: vec![vec![Inline::Text(text.to_string())]],
+            children,
+        };
+        assert_eq!(
+            doc.blocks,
+            vec![Block::List(List {
+                ordered: false,
+                items: vec![
+                    item(
+                        "a",
+                        vec![List {
+                            ordered: true,
+                            items: vec![item("b", Vec::new())],
+                        }]
+                    ),
+                    item("c", Vec::new()),
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn line_quotes_parse_their_contents() {
+        let doc = parse("> * a\n>> deeper\nafter");
+        assert_eq!(doc.blocks.len(), 2);
+        match &doc.blocks[0] {
+            Block::Quote(children) => {
+                assert!(matches!(children[0], Block::List(_)));
+                assert!(matches!(children[1], Block::Quote(_)));
+            }
+            other => panic!("expected quote, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn link_syntax_variants() {
+        let link = |text: &str, target: LinkTarget| {
+            vec![Inline::Link {
+                text: text.to_string(),
+                target,
+            }]
+        };
+        let url = |u: &str| LinkTarget::Url(u.to_string());
+        let page = |p: &str| LinkTarget::WikiPage(p.to_string());
+
+        assert_eq!(
+            parse_inline("[[Docs>https://a.example/x]]"),
+            link("Docs", url("https://a.example/x"))
+        );
+        assert_eq!(
+            parse_inline("[[Docs:https://a.example/x]]"),
+            link("Docs", url("https://a.example/x"))
+        );
+        assert_eq!(
+            parse_inline("[[https://a.example/x]]"),
+            link("https://a.example/x", url("https://a.example/x"))
+        );
+        assert_eq!(
+            parse_inline("[[See>Spec/API]]"),
+            link("See", page("Spec/API"))
+        );
+        assert_eq!(
+            parse_inline("[[Note:Sub]]"),
+            link("Note:Sub", page("Note:Sub"))
+        );
+    }
+
+    #[test]
+    fn warnings_report_constructs_with_line_numbers() {
+        let doc =
+            parse("intro\n{color:red}x{color}\n> [[Home]]\n|a|\n|b|h\n{quote}\n#attach(a.zip)");
+        let found: Vec<(usize, &str)> = doc
+            .warnings
+            .iter()
+            .map(|w| (w.line, w.construct.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, "{color}"),
+                (3, "[[wiki link]]"),
+                (5, "|h"),
+                (6, "{quote}"),
+                (7, "#attach"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use super::ast::{Block, Document, EmphasisStyle, Inline, LinkTarget, List, TableRow};
+use super::{ConvertOptions, IssueLinkStyle};
+use crate::export;
+
+pub fn render_document(document: &Document, options: &ConvertOptions) -> String {
+    let renderer = Renderer { options };
+    let mut lines = Vec::new();
+    renderer.render_blocks(&document.blocks, &mut lines);
+    lines.join("\n")
+}
+
+struct Renderer<'a> {
+    options: &'a ConvertOptions,
+}
+
+impl Renderer<'_> {
+    fn render_blocks(&self, blocks: &[Block], lines: &mut Vec<String>) {
+        for (i, block) in blocks.iter().enumerate() {
+            match block {
+                Block::Heading { level, content } => {
+                    lines.push(format!(
+                        "{} {}",
+                        "#".repeat(*level),
+                        self.render_inlines(content)
+                    ));
+                }
+                Block::List(list) => {
+                    self.render_list(list, "", lines);
+                    // Without a blank line, Markdown would read a following text
+                    // line as a lazy continuation of the last list item.
+                    if matches!(blocks.get(i + 1), Some(Block::Line(_) | Block::Table(_))) {
+                        lines.push(String::new());
+                    }
+                }
+                Block::Quote(children) => {
+                    self.render_quote(children, lines);
+                    // Same lazy continuation issue as lists; two quotes in a row
+                    // would also merge into one.
+                    if matches!(
+                        blocks.get(i + 1),
+                        Some(Block::Line(_) | Block::Table(_) | Block::Quote(_))
+                    ) {
+                        lines.push(String::new());
+                    }
+                }
+                Block::Code {
+                    language,
+                    lines: code,
+                } => {
+                    let fence = code_fence(code);
+                    lines.push(format!("{fence}{}", language.as_deref().unwrap_or("")));
+                    lines.extend(code.iter().cloned());
+                    lines.push(fence);
+                }
+                Block::Table(rows) => self.render_table(rows, lines),
+                Block::Line(content) => lines.push(escape_line_start(self.render_inlines(content))),
+                Block::Blank => lines.push(String::new()),
+            }
+        }
+    }
+
+    fn render_quote(&self, children: &[Block], lines: &mut Vec<String>) {
+        let mut inner = Vec::new();
+        self.render_blocks(children, &mut inner);
+        for line in inner {
+            if line.is_empty() {
+                lines.push(">".to_string());
+            } else {
+                lines.push(format!("> {line}"));
+            }
+        }
+    }
+
+    fn render_list(&self, list: &List, indent: &str, lines: &mut Vec<String>) {
+        for (i, item) in list.items.iter().enumerate() {
+            let marker = if list.ordered {
+                format!("{}. ", i + 1)
+            } else {
+                "- ".to_string()
+            };
+            let content_indent = format!("{indent}{}", " ".repeat(marker.len()));
+
+            for (j, line) in item.lines.iter().enumerate() {
+                let prefix = if j == 0 {
+                    format!("{indent}{marker}")
+                } else {
+                    content_indent.clone()
+                };
+                let content = escape_line_start(self.render_inlines(line));
+                lines.push(format!("{prefix}{content}"));
+            }
+            for child in &item.children {
+                self.render_list(child, &content_indent, lines);
+            }
+        }
+    }
+
+    // GFM needs exactly one header row, so a table without a Backlog `|h` row on
+    // top gets an empty header. Later header rows are rendered as ordinary rows.
+    fn render_table(&self, rows: &[TableRow], lines: &mut Vec<String>) {
+        let columns = rows.iter().map(|r| r.cells.len()).max().unwrap_or(0).max(1);
+        let (header, body) = match rows.split_first() {
+            Some((first, rest)) if first.header => (Some(first), rest),
+            _ => (None, rows),
+        };
+
+        lines.push(match header {
+            Some(row) => self.render_table_row(&row.cells, columns),
+            None => self.render_table_row(&[], columns),
+        });
+        lines.push(format!("|{}", " --- |".repeat(columns)));
+        for row in body {
+            lines.push(self.render_table_row(&row.cells, columns));
+        }
+    }
+
+    fn render_table_row(&self, cells: &[Vec<Inline>], columns: usize) -> String {
+        let rendered: Vec<String> = (0..columns)
+            .map(|i| {
+                cells
+                    .get(i)
+                    .map(|cell| self.render_inlines(cell).replace('|', "\\|"))
+                    .unwrap_or_default()
+            })
+            .collect();
+        format!("| {} |", rendered.join(" | "))
+    }
+
+    fn render_inlines(&self, inlines: &[Inline]) -> String {
+        let mut out = String::new();
+        for (i, inline) in inlines.iter().enumerate() {
+            match inline {
+                Inline::Text(text) => out.push_str(&self.link_issue_keys(text)),
+                Inline::Code(code) => out.push_str(&code_span(code)),
+                Inline::Link { text, target } => out.push_str(&self.render_link(text, target)),
+                Inline::Color(children) => out.push_str(&self.render_inlines(children)),
+                Inline::Image { name, thumbnail } => {
+                    out.push_str(&self.render_image(name, *thumbnail));
+                }
+                Inline::Emphasis { style, children } => {
+                    let prev = out.chars().last();
+                    let next = inlines.get(i + 1).and_then(first_char);
+                    out.push_str(&render_emphasis(
+                        *style,
+                        &self.render_inlines(children),
+                        prev,
+                        next,
+                    ));
+                }
+                Inline::LineBreak => out.push_str("<br>"),
+            }
+        }
+        out
+    }
+
+    // Wiki pages can only be linked when the space and project are known;
+    // otherwise the page name is kept as plain text.
+    fn render_link(&self, text: &str, target: &LinkTarget) -> String {
+        let url = match target {
+            LinkTarget::Url(url) => url.clone(),
+            LinkTarget::WikiPage(page) => match self.wiki_page_url(page) {
+                Some(url) => url,
+                None => return text.to_string(),
+            },
+        };
+
+        if text == url && !url.contains(['<', '>', ' ']) {
+            return format!("<{url}>");
+        }
+        format!("[{}]({})", escape_link_text(text), escape_link_url(&url))
+    }
+
+    // Attachments can only be shown when they are saved next to the exported
+    // file; otherwise the macro is kept as written. The link uses the name the
+    // file is saved under.
+    fn render_image(&self, name: &str, thumbnail: bool) -> String {
+        match self.options.asset_dir.as_deref() {
+            Some(dir) => format!(
+                "![{}]({})",
+                escape_link_text(name),
+                escape_link_url(&format!(
+                    "{}/{}",
+                    dir.trim_end_matches('/'),
+                    export::attachment_file_name(name)
+                ))
+            ),
+            None if thumbnail => format!("#thumbnail({name})"),
+            None => format!("#image({name})"),
+        }
+    }
+
+    // Escapes the text and links issue keys in it. Only keys of projects
+    // known to the space are linked, so text such as `UTF-8` or `ISO-9001`
+    // is left alone. A key glued to ASCII word characters or to a URL path is
+    // not a mention.
+    fn link_issue_keys(&self, text: &str) -> String {
+        if self.options.project_keys.is_empty() {
+            return escape_text(text);
+        }
+
+        let mut out = String::new();
+        let mut prev: Option<char> = None;
+        let mut plain = 0;
+        let mut pos = 0;
+        while let Some(c) = text[pos..].chars().next() {
+            let at_boundary = prev
+                .is_none_or(|p| !(p.is_ascii_alphanumeric() || matches!(p, '_' | '-' | '/' | '.')));
+            if let Some(len) = at_boundary
+                .then(|| self.issue_key_len(&text[pos..]))
+                .flatten()
+            {
+                if let Some(link) = self.issue_link(&text[pos..pos + len]) {
+                    out.push_str(&escape_text(&text[plain..pos]));
+                    out.push_str(&link);
+                    prev = text[pos..pos + len].chars().last();
+                    pos += len;
+                    plain = pos;
+                    continue;
+                }
+            }
+            prev = Some(c);
+            pos += c.len_utf8();
+        }
+        out.push_str(&escape_text(&text[plain..]));
+        out
+    }
+
+    fn issue_key_len(&self, text: &str) -> Option<usize> {
+        let key_len = text
+            .bytes()
+            .take_while(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_')
+            .count();
+        let project_key = &text[..key_len];
+        let after = text[key_len..].strip_prefix('-')?;
+        if !self.options.project_keys.iter().any(|k| k == project_key) {
+            return None;
+        }
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        let next = after[digits..].chars().next();
+        if digits == 0 || next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+        Some(project_key.len() + 1 + digits)
+    }
+
+    fn issue_link(&self, issue_key: &str) -> Option<String> {
+        let url = match self.options.issue_links {
+            IssueLinkStyle::Web => format!(
+                "{}/view/{issue_key}",
+                self.options.space_url.as_deref()?.trim_end_matches('/')
+            ),
+            IssueLinkStyle::File => format!("{issue_key}.md"),
+        };
+        Some(format!("[{issue_key}]({url})"))
+    }
+
+    fn wiki_page_url(&self, page: &str) -> Option<String> {
+        let space_url = self.options.space_url.as_deref()?;
+        let project_key = self.options.project_key.as_deref()?;
+        Some(format!(
+            "{}/wiki/{}/{}",
+            space_url.trim_end_matches('/'),
+            urlencoding::encode(project_key),
+            urlencoding::encode(page)
+        ))
+    }
+}
+
+// Surrounding whitespace is moved outside the delimiters. When the Markdown
+// delimiters could not open or close here (CommonMark flanking rules, which
+// commonly bite with Japanese brackets such as `**「重要」**です`), the span
+// falls back to the equivalent HTML tag.
+fn render_emphasis(
+    style: EmphasisStyle,
+    inner: &str,
+    prev: Option<char>,
+    next: Option<char>,
+) -> String {
+    let (delimiter, tag) = match style {
+        EmphasisStyle::Bold => ("**", "strong"),
+        EmphasisStyle::Italic => ("*", "em"),
+        EmphasisStyle::Strike => ("~~", "del"),
+    };
+
+    let core = inner.trim();
+    if core.is_empty() {
+        return inner.to_string();
+    }
+    let leading = &inner[..inner.len() - inner.trim_start().len()];
+    let trailing = &inner[inner.trim_end().len()..];
+    let before = if leading.is_empty() { prev } else { Some(' ') };
+    let after = if trailing.is_empty() { next } else { Some(' ') };
+
+    let marker = delimiter.chars().next();
+    let first = core.chars().next();
+    let last = core.chars().last();
+    let can_open = before != marker && (!is_punctuation(first) || is_boundary(before));
+    let can_close = after != marker && (!is_punctuation(last) || is_boundary(after));
+
+    if can_open && can_close {
+        format!("{leading}{delimiter}{core}{delimiter}{trailing}")
+    } else {
+        format!("{leading}<{tag}>{core}</{tag}>{trailing}")
+    }
+}
+
+fn first_char(inline: &Inline) -> Option<char> {
+    match inline {
+        Inline::Text(text) => text.chars().next(),
+        Inline::Code(_) => Some('`'),
+        Inline::Link { .. } => Some('['),
+        Inline::Color(children) => children.first().and_then(first_char),
+        Inline::Image { .. } => Some('!'),
+        Inline::Emphasis { children, .. } => match children.first().and_then(first_char) {
+            Some(c) if c.is_whitespace() => Some(c),
+            _ => Some('*'),
+        },
+        Inline::LineBreak => Some('<'),
+    }
+}
+
+fn is_punctuation(c: Option<char>) -> bool {
+    c.is_some_and(|c| !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+fn is_boundary(c: Option<char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace() || is_punctuation(Some(c)))
+}
+
+// Backslash-escapes what CommonMark would read as inline syntax. A marker
+// between spaces cannot open or close emphasis and an underscore inside a
+// word cannot either, so `a * b` and `snake_case` stay as they are.
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut prev: Option<char> = None;
+    for (i, c) in text.char_indices() {
+        let next = text[i + c.len_utf8()..].chars().next();
+        let escape = match c {
+            '\\' | '`' | '[' | '<' => true,
+            '*' | '~' => {
+                !(prev.is_some_and(char::is_whitespace) && next.is_none_or(char::is_whitespace))
+            }
+            '_' => {
+                !(prev.is_some_and(char::is_alphanumeric)
+                    && next.is_some_and(char::is_alphanumeric))
+            }
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+        prev = Some(c);
+    }
+    out
+}
+
+// Text at the start of a line that would open a block instead: a heading,
+// quote, list item, thematic break or setext underline. `#` is escaped even
+// without a space so tags are not read as headings by lenient renderers.
+fn escape_line_start(line: String) -> String {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return line;
+    }
+    let (lead, rest) = line.split_at(indent);
+    let marker_end = |len: usize| rest[len..].is_empty() || rest[len..].starts_with([' ', '\t']);
+
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..=9).contains(&digits) && rest[digits..].starts_with(['.', ')']) && marker_end(digits + 1)
+    {
+        return format!("{lead}{}\\{}", &rest[..digits], &rest[digits..]);
+    }
+    let escape = match rest.chars().next() {
+        Some('#' | '>') => true,
+        Some(c @ ('-' | '+' | '=')) => marker_end(1) || rest.chars().all(|r| r == c || r == ' '),
+        _ => false,
+    };
+    if escape {
+        format!("{lead}\\{rest}")
+    } else {
+        line
+    }
+}
+
+fn escape_link_text(text: &str) -> String {
+    text.replace('[', "\\[").replace(']', "\\]")
+}
+
+fn escape_link_url(url: &str) -> String {
+    url.replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+fn code_fence(code: &[String]) -> String {
+    let longest_run = code
+        .iter()
+        .map(|line| longest_backtick_run(line))
+        .max()
+        .unwrap_or(0);
+    "`".repeat((longest_run + 1).max(3))
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+// Uses a backtick fence longer than any backtick run in the code, padding with
+// spaces when the code starts or ends with a backtick (CommonMark code spans).
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_backtick_run(code) + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{fence} {code} {fence}")
+    } else {
+        format!("{fence}{code}{fence}")
+    }
+}
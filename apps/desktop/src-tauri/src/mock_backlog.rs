@@ -0,0 +1,189 @@
+// An in-process stand-in for the Backlog API, for tests. Answers each path
+// (query string ignored) with the reply registered for it, 404 otherwise,
+// until dropped.
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+use crate::backlog::BacklogClient;
+
+pub const API_KEY: &str = "mock-api-key";
+
+#[derive(Clone)]
+struct Reply {
+    status: StatusCode,
+    body: String,
+}
+
+#[derive(Default)]
+struct Shared {
+    replies: HashMap<String, Reply>,
+    requests: Vec<String>,
+}
+
+pub struct MockBacklog {
+    base_url: String,
+    shared: Arc<Mutex<Shared>>,
+    stop: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl MockBacklog {
+    // Starts with the endpoints every space has: the signed-in user and an
+    // empty project list.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        listener.set_nonblocking(true).expect("nonblocking");
+        let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let server = {
+            let shared = Arc::clone(&shared);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => serve(stream, &shared),
+                        Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(5));
+                        }
+                        Err(e) => panic!("accept: {e}"),
+                    }
+                }
+            })
+        };
+
+        let mock = Self {
+            base_url,
+            shared,
+            stop,
+            server: Some(server),
+        };
+        mock.respond(
+            "/api/v2/users/myself",
+            json!({"id": 1, "name": "テストユーザー"}),
+        );
+        mock.respond("/api/v2/projects", json!([]));
+        mock
+    }
+
+    pub fn client(&self) -> BacklogClient {
+        BacklogClient::new(&self.base_url, API_KEY).expect("client")
+    }
+
+    pub fn respond(&self, path: &str, body: Value) {
+        self.reply(path, StatusCode::OK, body.to_string());
+    }
+
+    pub fn fail(&self, path: &str, status: StatusCode) {
+        let body = json!({"errors": [{"message": "mock error", "code": 0}]});
+        self.reply(path, status, body.to_string());
+    }
+
+    // Paths requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.shared.lock().expect("lock").requests.clone()
+    }
+
+    fn reply(&self, path: &str, status: StatusCode, body: String) {
+        self.shared
+            .lock()
+            .expect("lock")
+            .replies
+            .insert(path.to_string(), Reply { status, body });
+    }
+}
+
+impl Drop for MockBacklog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, shared: &Mutex<Shared>) {
+    stream.set_nonblocking(false).expect("blocking");
+    let Some(target) = read_target(&mut stream) else {
+        return;
+    };
+    let path = target.split('?').next().unwrap_or_default().to_string();
+
+    let reply = {
+        let mut shared = shared.lock().expect("lock");
+        shared.requests.push(path.clone());
+        shared.replies.get(&path).cloned()
+    }
+    .unwrap_or_else(|| Reply {
+        status: StatusCode::NOT_FOUND,
+        body: json!({"errors": [{"message": "No such resource.", "code": 6}]}).to_string(),
+    });
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        reply.status,
+        reply.body.len(),
+        reply.body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+// The request target of a GET; the client only sends GETs without bodies.
+fn read_target(stream: &mut TcpStream) -> Option<String> {
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).ok().filter(|&n| n > 0)?;
+        data.extend_from_slice(&buf[..read]);
+    }
+    let head = String::from_utf8_lossy(&data);
+    head.lines().next()?.split(' ').nth(1).map(str::to_string)
+}
+
+pub fn project(id: i64, project_key: &str) -> Value {
+    json!({
+        "id": id,
+        "projectKey": project_key,
+        "name": format!("{project_key} プロジェクト"),
+        "textFormattingRule": "markdown",
+    })
+}
+
+pub fn issue(issue_key: &str, summary: &str, description: &str) -> Value {
+    json!({
+        "id": 1,
+        "projectId": 1,
+        "issueKey": issue_key,
+        "summary": summary,
+        "description": description,
+        "created": "2026-01-01T00:00:00Z",
+        "updated": "2026-01-02T00:00:00Z",
+        "issueType": {"id": 1, "name": "タスク"},
+        "status": {"id": 1, "name": "未対応"},
+        "priority": {"id": 3, "name": "中"},
+        "assignee": null,
+        "createdUser": {"id": 1, "name": "テストユーザー"},
+        "category": [],
+        "versions": [],
+        "milestone": [],
+    })
+}
+
+pub fn comment(id: i64, content: &str) -> Value {
+    json!({
+        "id": id,
+        "content": content,
+        "createdUser": {"id": 1, "name": "テストユーザー"},
+        "created": "2026-01-03T00:00:00Z",
+        "updated": "2026-01-03T00:00:00Z",
+    })
+}
@@ -6,6 +6,7 @@ pub struct Project {
     pub id: i64,
     pub project_key: String,
     pub name: String,
+    pub text_formatting_rule: String,
     pub synced_at: String,
 }
 
@@ -17,7 +18,17 @@ pub struct IssueSummary {
     pub updated_at: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// One page of keyword search results. `total` counts every match, so the UI
+// knows whether there is more to load; it comes with the first page only.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSearchPage {
+    pub issues: Vec<IssueSummary>,
+    pub total: Option<u64>,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct IssueDetail {
     pub issue_key: String,
@@ -26,6 +37,167 @@ pub struct IssueDetail {
     pub description_md: String,
     pub updated_at: String,
     pub synced_at: String,
+    pub conversion_warnings: Vec<ConversionWarning>,
+    pub issue_type: Option<String>,
+    pub status: Option<String>,
+    pub priority: Option<String>,
+    pub assignee: Option<String>,
+    pub created_user: Option<String>,
+    pub created_at: Option<String>,
+    pub due_date: Option<String>,
+    pub parent_issue_key: Option<String>,
+    pub categories: Vec<String>,
+    pub versions: Vec<String>,
+    pub milestones: Vec<String>,
+    pub facets: IssueFacets,
+}
+
+// Ids of the values issues are filtered by. They are cached with the issue so
+// offline searches can apply the same filters as the API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueFacets {
+    pub project_id: Option<i64>,
+    pub status_id: Option<i64>,
+    pub assignee_id: Option<i64>,
+    pub issue_type_id: Option<i64>,
+    pub category_ids: Vec<i64>,
+    pub milestone_ids: Vec<i64>,
+}
+
+// A search hit with what the cache needs to filter and sort it offline.
+#[derive(Debug, Clone)]
+pub struct IssueListing {
+    pub summary: IssueSummary,
+    pub created_at: Option<String>,
+    pub due_date: Option<String>,
+    pub facets: IssueFacets,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSort {
+    Created,
+    #[default]
+    Updated,
+    DueDate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Search conditions, mapped onto the `/api/v2/issues` parameters of the same
+// names. Empty lists and unset values do not filter. Dates are `yyyy-MM-dd`
+// and inclusive.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IssueFilter {
+    pub keyword: Option<String>,
+    pub project_ids: Vec<i64>,
+    pub status_ids: Vec<i64>,
+    pub assignee_ids: Vec<i64>,
+    pub issue_type_ids: Vec<i64>,
+    pub category_ids: Vec<i64>,
+    pub milestone_ids: Vec<i64>,
+    pub created_since: Option<String>,
+    pub created_until: Option<String>,
+    pub updated_since: Option<String>,
+    pub updated_until: Option<String>,
+    pub sort: IssueSort,
+    pub order: SortOrder,
+}
+
+impl IssueFilter {
+    pub fn keyword(&self) -> Option<&str> {
+        self.keyword
+            .as_deref()
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedId {
+    pub id: i64,
+    pub name: String,
+}
+
+// Choices for the filters of one project.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterOptions {
+    pub statuses: Vec<NamedId>,
+    pub assignees: Vec<NamedId>,
+    pub issue_types: Vec<NamedId>,
+    pub categories: Vec<NamedId>,
+    pub milestones: Vec<NamedId>,
+}
+
+// A wiki page as listed, without its content. Names use `/` for the page
+// hierarchy, e.g. `設計/API`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiSummary {
+    pub id: i64,
+    pub project_id: i64,
+    pub name: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiPage {
+    pub id: i64,
+    pub project_id: i64,
+    pub name: String,
+    pub content_raw: String,
+    pub content_md: String,
+    pub tags: Vec<String>,
+    pub updated_at: String,
+    pub synced_at: String,
+    pub conversion_warnings: Vec<ConversionWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    pub id: i64,
+    pub issue_key: String,
+    pub author: String,
+    pub content_raw: String,
+    pub content_md: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: i64,
+    pub name: String,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionWarning {
+    pub construct: String,
+    pub line: usize,
+    pub action: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionReport {
+    pub issue_key: String,
+    pub summary: String,
+    pub warnings: Vec<ConversionWarning>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +207,100 @@ pub struct ExportHistory {
     pub issue_key: String,
     pub export_path: String,
     pub exported_at: String,
+    pub error: Option<String>,
+}
+
+// What a bulk export covers: a whole project, a list of keys, or the issues
+// matching a keyword (optionally within one project).
+#[derive(Debug, Clone, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum BulkExportTarget {
+    Project {
+        project_key: String,
+    },
+    Keys {
+        issue_keys: Vec<String>,
+    },
+    Search {
+        keyword: String,
+        project_key: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkExportItem {
+    pub issue_key: String,
+    pub path: Option<String>,
+    pub attachment_count: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkExportResult {
+    pub exported: usize,
+    pub failed: usize,
+    pub items: Vec<BulkExportItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiExportItem {
+    pub wiki_id: i64,
+    pub name: String,
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WikiExportResult {
+    pub exported: usize,
+    pub failed: usize,
+    pub items: Vec<WikiExportItem>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthMethod {
+    #[default]
+    ApiKey,
+    #[serde(rename = "oauth")]
+    OAuth,
+}
+
+impl AuthMethod {
+    pub fn key(self) -> &'static str {
+        match self {
+            AuthMethod::ApiKey => "api_key",
+            AuthMethod::OAuth => "oauth",
+        }
+    }
+}
+
+// How `setup_save` signs in to the space. With OAuth the client id and secret
+// are those of the app registered in Backlog; the tokens come from the
+// authorization flow. No `Debug`, as both variants carry secrets.
+#[derive(Clone, Deserialize)]
+#[serde(
+    tag = "method",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SetupAuth {
+    ApiKey {
+        api_key: String,
+    },
+    #[serde(rename = "oauth")]
+    OAuth {
+        client_id: String,
+        client_secret: String,
+    },
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -42,5 +308,91 @@ pub struct ExportHistory {
 pub struct SetupState {
     pub space_url: Option<String>,
     pub has_api_key: bool,
+    pub auth_method: AuthMethod,
+    // To be registered as the app's redirect URI in Backlog.
+    pub oauth_redirect_uri: String,
     pub export_dir: Option<String>,
+    pub front_matter: FrontMatterSettings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub id: i64,
+    pub name: String,
+    pub active: bool,
+}
+
+// The API budget from Backlog's X-RateLimit-* headers; all fields are unset
+// until the first response of the session.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitStatus {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<String>,
+    // Set while a request is held back to stay within the budget.
+    pub waiting_until: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTemplate {
+    pub template: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrontMatterField {
+    IssueKey,
+    Summary,
+    Status,
+    Assignee,
+    Url,
+    UpdatedAt,
+    ExportedAt,
+    Tags,
+}
+
+impl FrontMatterField {
+    pub const ALL: [FrontMatterField; 8] = [
+        FrontMatterField::IssueKey,
+        FrontMatterField::Summary,
+        FrontMatterField::Status,
+        FrontMatterField::Assignee,
+        FrontMatterField::Url,
+        FrontMatterField::UpdatedAt,
+        FrontMatterField::ExportedAt,
+        FrontMatterField::Tags,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            FrontMatterField::IssueKey => "issue_key",
+            FrontMatterField::Summary => "summary",
+            FrontMatterField::Status => "status",
+            FrontMatterField::Assignee => "assignee",
+            FrontMatterField::Url => "url",
+            FrontMatterField::UpdatedAt => "updated_at",
+            FrontMatterField::ExportedAt => "exported_at",
+            FrontMatterField::Tags => "tags",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontMatterSettings {
+    pub enabled: bool,
+    pub fields: Vec<FrontMatterField>,
+}
+
+impl Default for FrontMatterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fields: FrontMatterField::ALL.to_vec(),
+        }
+    }
 }
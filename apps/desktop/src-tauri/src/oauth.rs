@@ -0,0 +1,681 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use reqwest::blocking::Client;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::app_error::{AppError, AppResult};
+use crate::backlog;
+
+// The redirect URI registered for the app in Backlog must match exactly, so
+// the loopback listener uses a fixed port.
+pub const REDIRECT_PORT: u16 = 43175;
+// How long the browser has to come back after the consent screen opens.
+const AUTHORIZE_TIMEOUT: Duration = Duration::from_secs(300);
+// How long one connection to the loopback listener may take to send its
+// request before it is dropped.
+const CALLBACK_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+// No `Debug`: these hold secrets and must not end up in logs by accident.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthApp {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+}
+
+// What is kept in the keychain per space: refreshing needs the app's
+// credentials as well as the tokens.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthCredentials {
+    pub app: OAuthApp,
+    pub tokens: OAuthTokens,
+}
+
+// One profile's credentials, shared by all of its clients: Backlog rotates
+// refresh tokens, so only one of them may refresh and the rest must pick up
+// the new tokens.
+pub type SharedCredentials = Arc<Mutex<OAuthCredentials>>;
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+}
+
+pub fn redirect_uri(port: u16) -> String {
+    format!("http://127.0.0.1:{port}/callback")
+}
+
+pub fn authorization_url(
+    space_url: &str,
+    app: &OAuthApp,
+    redirect_uri: &str,
+    state: &str,
+    code_verifier: &str,
+) -> String {
+    let mut url = format!(
+        "{}/OAuth2AccessRequest.action",
+        space_url.trim_end_matches('/')
+    );
+    url.push_str(&format!(
+        "?response_type=code&client_id={}&redirect_uri={}&state={}&code_challenge={}&code_challenge_method=S256",
+        urlencoding::encode(&app.client_id),
+        urlencoding::encode(redirect_uri),
+        urlencoding::encode(state),
+        code_challenge(code_verifier)
+    ));
+    url
+}
+
+// PKCE (RFC 7636): the code is only good together with the verifier, which
+// never leaves the app.
+pub fn code_challenge(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+// Runs the authorization-code flow: listens on the loopback redirect, lets
+// `open_browser` show Backlog's consent screen and exchanges the returned
+// code for tokens. Port 0 picks a free port, for tests.
+pub fn authorize(
+    space_url: &str,
+    app: &OAuthApp,
+    port: u16,
+    open_browser: impl FnOnce(&str) -> AppResult<()>,
+) -> AppResult<OAuthTokens> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    let redirect_uri = redirect_uri(listener.local_addr()?.port());
+    let state = random_token()?;
+    let code_verifier = random_token()?;
+
+    open_browser(&authorization_url(
+        space_url,
+        app,
+        &redirect_uri,
+        &state,
+        &code_verifier,
+    ))?;
+    let code = wait_for_code(&listener, &state, AUTHORIZE_TIMEOUT, CALLBACK_READ_TIMEOUT)?;
+    exchange_code(
+        &token_client()?,
+        space_url,
+        app,
+        &code,
+        &redirect_uri,
+        &code_verifier,
+    )
+}
+
+pub fn exchange_code(
+    client: &Client,
+    space_url: &str,
+    app: &OAuthApp,
+    code: &str,
+    redirect_uri: &str,
+    code_verifier: &str,
+) -> AppResult<OAuthTokens> {
+    request_tokens(
+        client,
+        space_url,
+        app,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("code_verifier", code_verifier),
+        ],
+    )
+}
+
+// Backlog rotates refresh tokens: the one returned here replaces the old,
+// which stops working.
+pub fn refresh_tokens(
+    client: &Client,
+    space_url: &str,
+    app: &OAuthApp,
+    refresh_token: &str,
+) -> AppResult<OAuthTokens> {
+    let tokens = request_tokens(
+        client,
+        space_url,
+        app,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ],
+    )?;
+    Ok(OAuthTokens {
+        refresh_token: tokens
+            .refresh_token
+            .or_else(|| Some(refresh_token.to_string())),
+        ..tokens
+    })
+}
+
+fn request_tokens(
+    client: &Client,
+    space_url: &str,
+    app: &OAuthApp,
+    grant: &[(&str, &str)],
+) -> AppResult<OAuthTokens> {
+    let url = format!("{}/api/v2/oauth2/token", space_url.trim_end_matches('/'));
+    let mut form = vec![
+        ("client_id", app.client_id.as_str()),
+        ("client_secret", app.client_secret.as_str()),
+    ];
+    form.extend_from_slice(grant);
+
+    let response = client.post(url).form(&form).send()?;
+    // A rejected code or refresh token means signing in again.
+    if matches!(
+        response.status(),
+        StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED
+    ) {
+        return Err(AppError::AuthInvalid);
+    }
+    backlog::map_status_code(response.status())?;
+    let payload: TokenResponse = response.json().map_err(AppError::from)?;
+    Ok(OAuthTokens {
+        access_token: payload.access_token,
+        refresh_token: payload.refresh_token,
+    })
+}
+
+fn token_client() -> AppResult<Client> {
+    Ok(Client::builder()
+        .connect_timeout(Duration::from_secs(8))
+        .timeout(Duration::from_secs(20))
+        .build()?)
+}
+
+// Waits for the browser to be redirected back with `?code=…&state=…`.
+// Other requests, such as a favicon, get a 404 and are ignored, as are
+// connections that fail or send nothing in time.
+fn wait_for_code(
+    listener: &TcpListener,
+    state: &str,
+    timeout: Duration,
+    read_timeout: Duration,
+) -> AppResult<String> {
+    listener.set_nonblocking(true)?;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if Instant::now() >= deadline {
+            return Err(AppError::Validation(
+                "timed out waiting for the Backlog authorization".to_string(),
+            ));
+        }
+        let mut stream = match listener.accept() {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                if e.kind() != ErrorKind::WouldBlock {
+                    log::warn!("loopback accept failed: {e}");
+                }
+                thread::sleep(Duration::from_millis(100));
+                continue;
+            }
+        };
+
+        let params = match read_callback(&mut stream, read_timeout) {
+            Ok(Some(params)) => params,
+            Ok(None) => {
+                respond(&mut stream, "404 Not Found", "Not Found");
+                continue;
+            }
+            Err(e) => {
+                log::warn!("ignoring a loopback connection: {e}");
+                continue;
+            }
+        };
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if param("state") != Some(state) {
+            respond(
+                &mut stream,
+                "400 Bad Request",
+                "認証リクエストが一致しません。",
+            );
+            return Err(AppError::Validation(
+                "OAuth state does not match".to_string(),
+            ));
+        }
+        if let Some(error) = param("error") {
+            respond(
+                &mut stream,
+                "200 OK",
+                "認証がキャンセルされました。アプリに戻ってください。",
+            );
+            return Err(AppError::Validation(format!(
+                "authorization failed: {error}"
+            )));
+        }
+        let Some(code) = param("code") else {
+            respond(&mut stream, "400 Bad Request", "認可コードがありません。");
+            return Err(AppError::Validation(
+                "authorization code is missing".to_string(),
+            ));
+        };
+        respond(
+            &mut stream,
+            "200 OK",
+            "認証が完了しました。アプリに戻ってください。",
+        );
+        return Ok(code.to_string());
+    }
+}
+
+// The query of a `GET /callback` request; `None` for any other request.
+fn read_callback(
+    stream: &mut TcpStream,
+    read_timeout: Duration,
+) -> AppResult<Option<Vec<(String, String)>>> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(read_timeout))?;
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < 16 * 1024 {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let (Some("GET"), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let Ok(url) = Url::parse(&format!("http://127.0.0.1{target}")) else {
+        return Ok(None);
+    };
+    if url.path() != "/callback" {
+        return Ok(None);
+    }
+    Ok(Some(url.query_pairs().into_owned().collect()))
+}
+
+// The browser may already have given up on the page; that does not change
+// the outcome of signing in.
+fn respond(stream: &mut TcpStream, status: &str, message: &str) {
+    let body = format!(
+        "<!doctype html><html lang=\"ja\"><meta charset=\"utf-8\"><title>Backlog Markdown Exporter</title><p>{message}</p></html>"
+    );
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        log::warn!("cannot answer the loopback request: {e}");
+    }
+}
+
+// 32 bytes from the OS's secure random source, URL-safe: the `state` that
+// ties the redirect to this attempt, and the PKCE code verifier.
+fn random_token() -> AppResult<String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| AppError::Unknown(format!("no secure random source: {e}")))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    use reqwest::Url;
+
+    use super::{
+        authorize, code_challenge, wait_for_code, OAuthApp, OAuthCredentials, OAuthTokens,
+    };
+    use crate::app_error::AppError;
+    use crate::backlog::{BacklogApi, BacklogClient};
+
+    struct Request {
+        method: String,
+        target: String,
+        authorization: Option<String>,
+        body: String,
+    }
+
+    // A stand-in for the Backlog authorization server and API. Answers
+    // `requests` requests with `handler`, one connection each.
+    fn stand_in(
+        requests: usize,
+        handler: impl Fn(&Request) -> String + Send + 'static,
+    ) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let base_url = format!("http://{}", listener.local_addr().expect("addr"));
+        let server = thread::spawn(move || {
+            for _ in 0..requests {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let request = read_request(&mut stream);
+                stream
+                    .write_all(handler(&request).as_bytes())
+                    .expect("respond");
+            }
+        });
+        (base_url, server)
+    }
+
+    fn read_request(stream: &mut TcpStream) -> Request {
+        let mut data = Vec::new();
+        let mut buf = [0; 1024];
+        let head_end = loop {
+            let read = stream.read(&mut buf).expect("read");
+            data.extend_from_slice(&buf[..read]);
+            if let Some(i) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break i + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+        let header = |name: &str| {
+            head.lines()
+                .filter_map(|line| line.split_once(": "))
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.to_string())
+        };
+        let length: usize = header("content-length").map_or(0, |v| v.parse().expect("length"));
+        while data.len() < head_end + length {
+            let read = stream.read(&mut buf).expect("read body");
+            data.extend_from_slice(&buf[..read]);
+        }
+
+        let mut request_line = head.lines().next().expect("request line").split(' ');
+        Request {
+            method: request_line.next().expect("method").to_string(),
+            target: request_line.next().expect("target").to_string(),
+            authorization: header("authorization"),
+            body: String::from_utf8_lossy(&data[head_end..]).to_string(),
+        }
+    }
+
+    fn json(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn app() -> OAuthApp {
+        OAuthApp {
+            client_id: "desktop".to_string(),
+            client_secret: "client-secret".to_string(),
+        }
+    }
+
+    #[test]
+    fn authorization_code_flow_uses_the_loopback_redirect() {
+        let challenge = Arc::new(Mutex::new(String::new()));
+        let sent = Arc::clone(&challenge);
+        let (space_url, server) = stand_in(2, move |request| match request.method.as_str() {
+            // The consent screen, approved at once.
+            "GET" => {
+                let url = Url::parse(&format!("http://x{}", request.target)).expect("url");
+                assert_eq!(url.path(), "/OAuth2AccessRequest.action");
+                let param = |name: &str| {
+                    url.query_pairs()
+                        .find(|(key, _)| key == name)
+                        .map(|(_, value)| value.to_string())
+                        .expect(name)
+                };
+                assert_eq!(param("client_id"), "desktop");
+                assert_eq!(param("code_challenge_method"), "S256");
+                *sent.lock().expect("lock") = param("code_challenge");
+                format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {}?code=the-code&state={}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    param("redirect_uri"),
+                    param("state")
+                )
+            }
+            _ => {
+                assert_eq!(request.target, "/api/v2/oauth2/token");
+                assert!(request.body.contains("grant_type=authorization_code"));
+                assert!(request.body.contains("code=the-code"));
+                assert!(request.body.contains("client_secret=client-secret"));
+                let verifier = request
+                    .body
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("code_verifier="))
+                    .expect("code_verifier");
+                assert_eq!(code_challenge(verifier), *sent.lock().expect("lock"));
+                json(
+                    "200 OK",
+                    r#"{"access_token":"access-1","token_type":"Bearer","expires_in":3600,"refresh_token":"refresh-1"}"#,
+                )
+            }
+        });
+
+        // The "browser" follows the consent screen's redirect back to us.
+        let browser = Arc::new(Mutex::new(None));
+        let opened = Arc::clone(&browser);
+        let tokens = authorize(&space_url, &app(), 0, move |url| {
+            let url = url.to_string();
+            *opened.lock().expect("lock") = Some(thread::spawn(move || {
+                let page = reqwest::blocking::get(url).expect("redirect");
+                page.text().expect("page")
+            }));
+            Ok(())
+        });
+        let page = browser
+            .lock()
+            .expect("lock")
+            .take()
+            .expect("browser opened")
+            .join()
+            .expect("browser");
+        server.join().expect("server");
+
+        let tokens = tokens.map_err(|e| e.to_string()).expect("tokens");
+        assert_eq!(tokens.access_token, "access-1");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+        assert!(page.contains("認証が完了しました"));
+    }
+
+    #[test]
+    fn pkce_challenge_is_the_s256_of_the_verifier() {
+        // RFC 7636, appendix B.
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn broken_loopback_connections_do_not_end_the_wait() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let browser = thread::spawn(move || {
+            drop(TcpStream::connect(addr).expect("closed at once"));
+            let _silent = TcpStream::connect(addr).expect("sends nothing");
+            let mut callback = TcpStream::connect(addr).expect("callback");
+            callback
+                .write_all(b"GET /callback?code=the-code&state=s1 HTTP/1.1\r\n\r\n")
+                .expect("request");
+            let mut page = String::new();
+            callback.read_to_string(&mut page).expect("page");
+            page
+        });
+
+        let code = wait_for_code(
+            &listener,
+            "s1",
+            Duration::from_secs(10),
+            Duration::from_millis(100),
+        );
+        let page = browser.join().expect("browser");
+        assert_eq!(code.map_err(|e| e.to_string()).expect("code"), "the-code");
+        assert!(page.contains("認証が完了しました"));
+    }
+
+    #[test]
+    fn expired_access_tokens_are_refreshed_and_rotated() {
+        let (space_url, server) = stand_in(3, |request| {
+            match (request.method.as_str(), request.authorization.as_deref()) {
+                ("GET", Some("Bearer access-1")) => json("401 Unauthorized", "{}"),
+                ("POST", _) => {
+                    assert!(request.body.contains("grant_type=refresh_token"));
+                    assert!(request.body.contains("refresh_token=refresh-1"));
+                    json(
+                        "200 OK",
+                        r#"{"access_token":"access-2","token_type":"Bearer","expires_in":3600,"refresh_token":"refresh-2"}"#,
+                    )
+                }
+                ("GET", Some("Bearer access-2")) => {
+                    assert_eq!(request.target, "/api/v2/users/myself");
+                    json("200 OK", r#"{"id":1,"name":"me"}"#)
+                }
+                other => panic!("unexpected request: {other:?}"),
+            }
+        });
+
+        let saved = Arc::new(Mutex::new(None));
+        let store = Arc::clone(&saved);
+        let client = BacklogClient::with_oauth(
+            &space_url,
+            OAuthCredentials {
+                app: app(),
+                tokens: OAuthTokens {
+                    access_token: "access-1".to_string(),
+                    refresh_token: Some("refresh-1".to_string()),
+                },
+            },
+            move |credentials| {
+                *store.lock().expect("lock") = Some(credentials.tokens.clone());
+                Ok(())
+            },
+        )
+        .expect("client");
+
+        client.verify_connection().expect("verified after refresh");
+        server.join().expect("server");
+
+        let saved = saved.lock().expect("lock").take().expect("tokens saved");
+        assert_eq!(saved.access_token, "access-2");
+        assert_eq!(saved.refresh_token.as_deref(), Some("refresh-2"));
+    }
+
+    #[test]
+    fn clients_sharing_credentials_refresh_only_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let space_url = format!("http://{}", listener.local_addr().expect("addr"));
+        let server = thread::spawn(move || {
+            // Both clients send their first request before either is answered.
+            let expired: Vec<TcpStream> = (0..2)
+                .map(|_| {
+                    let (mut stream, _) = listener.accept().expect("accept");
+                    let request = read_request(&mut stream);
+                    assert_eq!(request.authorization.as_deref(), Some("Bearer access-1"));
+                    stream
+                })
+                .collect();
+            for mut stream in expired {
+                stream
+                    .write_all(json("401 Unauthorized", "{}").as_bytes())
+                    .expect("respond");
+            }
+            for _ in 0..3 {
+                let (mut stream, _) = listener.accept().expect("accept");
+                let request = read_request(&mut stream);
+                let response = match request.method.as_str() {
+                    "POST" => {
+                        assert!(request.body.contains("refresh_token=refresh-1"));
+                        json(
+                            "200 OK",
+                            r#"{"access_token":"access-2","token_type":"Bearer","expires_in":3600,"refresh_token":"refresh-2"}"#,
+                        )
+                    }
+                    _ => {
+                        assert_eq!(request.authorization.as_deref(), Some("Bearer access-2"));
+                        json("200 OK", r#"{"id":1,"name":"me"}"#)
+                    }
+                };
+                stream.write_all(response.as_bytes()).expect("respond");
+            }
+        });
+
+        let shared = Arc::new(Mutex::new(OAuthCredentials {
+            app: app(),
+            tokens: OAuthTokens {
+                access_token: "access-1".to_string(),
+                refresh_token: Some("refresh-1".to_string()),
+            },
+        }));
+        let saves = Arc::new(Mutex::new(0));
+        let client = || {
+            let saves = Arc::clone(&saves);
+            BacklogClient::with_shared_oauth(&space_url, Arc::clone(&shared), move |_| {
+                *saves.lock().expect("lock") += 1;
+                Ok(())
+            })
+            .expect("client")
+        };
+        let (first, second) = (client(), client());
+        thread::scope(|scope| {
+            let first = scope.spawn(|| first.verify_connection());
+            let second = scope.spawn(|| second.verify_connection());
+            for result in [first.join(), second.join()] {
+                result
+                    .expect("client thread")
+                    .map_err(|e| e.to_string())
+                    .expect("verified");
+            }
+        });
+        server.join().expect("server");
+
+        assert_eq!(*saves.lock().expect("lock"), 1);
+        let tokens = shared.lock().expect("lock").tokens.clone();
+        assert_eq!(tokens.access_token, "access-2");
+        assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-2"));
+    }
+
+    #[test]
+    fn rejected_refresh_tokens_require_signing_in_again() {
+        let (space_url, server) = stand_in(2, |request| match request.method.as_str() {
+            "GET" => json("401 Unauthorized", "{}"),
+            _ => json("400 Bad Request", r#"{"error":"invalid_grant"}"#),
+        });
+
+        let client = BacklogClient::with_oauth(
+            &space_url,
+            OAuthCredentials {
+                app: app(),
+                tokens: OAuthTokens {
+                    access_token: "expired".to_string(),
+                    refresh_token: Some("revoked".to_string()),
+                },
+            },
+            |_| panic!("nothing to save"),
+        )
+        .expect("client");
+
+        let error = client.verify_connection().expect_err("sign in again");
+        server.join().expect("server");
+        assert!(matches!(error, AppError::AuthInvalid));
+    }
+}
@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path;
+
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::app_error::{AppError, AppResult};
+use crate::models::Profile;
+
+// Created on first run. It keeps the database file and Keychain entries from
+// before there were profiles, so an existing setup carries over as is.
+pub const FIRST_PROFILE_ID: i64 = 1;
+const FIRST_PROFILE_NAME: &str = "既定";
+
+// Which profiles exist and which one is in use. Everything else belongs to a
+// profile and lives in that profile's own database.
+pub struct ProfileStore {
+    conn: Connection,
+}
+
+impl ProfileStore {
+    pub fn open(path: &Path) -> AppResult<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let store = Self {
+            conn: Connection::open(path)?,
+        };
+        store.init_schema()?;
+        Ok(store)
+    }
+
+    fn init_schema(&self) -> AppResult<()> {
+        // AUTOINCREMENT: a new profile never reuses the id, and so the files
+        // and Keychain entries, of a deleted one.
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS profiles (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                created_at TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS profile_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            ",
+        )?;
+        self.conn.execute(
+            "INSERT INTO profiles(id, name, created_at)
+             SELECT ?1, ?2, ?3 WHERE NOT EXISTS (SELECT 1 FROM profiles)",
+            params![
+                FIRST_PROFILE_ID,
+                FIRST_PROFILE_NAME,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> AppResult<Vec<Profile>> {
+        let active_id = self.active_id()?;
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, name FROM profiles ORDER BY id")?;
+        let rows = stmt.query_map([], |row| {
+            let id: i64 = row.get(0)?;
+            Ok(Profile {
+                id,
+                name: row.get(1)?,
+                active: id == active_id,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // Falls back to the oldest profile when none was chosen yet.
+    pub fn active_id(&self) -> AppResult<i64> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT p.id FROM profile_settings s JOIN profiles p ON p.id = CAST(s.value AS INTEGER)
+                 WHERE s.key = 'active_profile'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match id {
+            Some(id) => Ok(id),
+            None => Ok(self
+                .conn
+                .query_row("SELECT MIN(id) FROM profiles", [], |row| row.get(0))?),
+        }
+    }
+
+    pub fn create(&self, name: &str) -> AppResult<Profile> {
+        let name = self.validate_name(name, None)?;
+        self.conn.execute(
+            "INSERT INTO profiles(name, created_at) VALUES(?1, ?2)",
+            params![name, Utc::now().to_rfc3339()],
+        )?;
+        Ok(Profile {
+            id: self.conn.last_insert_rowid(),
+            name,
+            active: false,
+        })
+    }
+
+    pub fn rename(&self, id: i64, name: &str) -> AppResult<()> {
+        let name = self.validate_name(name, Some(id))?;
+        let updated = self.conn.execute(
+            "UPDATE profiles SET name = ?1 WHERE id = ?2",
+            params![name, id],
+        )?;
+        if updated == 0 {
+            return Err(AppError::NotFound);
+        }
+        Ok(())
+    }
+
+    pub fn switch(&self, id: i64) -> AppResult<()> {
+        self.ensure_exists(id)?;
+        self.conn.execute(
+            "INSERT INTO profile_settings(key, value) VALUES('active_profile', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![id.to_string()],
+        )?;
+        Ok(())
+    }
+
+    // The last profile cannot go. Deleting the active one makes the oldest
+    // remaining profile active.
+    pub fn delete(&self, id: i64) -> AppResult<()> {
+        self.ensure_exists(id)?;
+        let count: i64 = self
+            .conn
+            .query_row("SELECT COUNT(*) FROM profiles", [], |row| row.get(0))?;
+        if count <= 1 {
+            return Err(AppError::Validation(
+                "the last profile cannot be deleted".to_string(),
+            ));
+        }
+
+        let was_active = self.active_id()? == id;
+        self.conn
+            .execute("DELETE FROM profiles WHERE id = ?1", params![id])?;
+        if was_active {
+            self.conn.execute(
+                "DELETE FROM profile_settings WHERE key = 'active_profile'",
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn ensure_exists(&self, id: i64) -> AppResult<()> {
+        self.conn
+            .query_row("SELECT 1 FROM profiles WHERE id = ?1", params![id], |_| {
+                Ok(())
+            })
+            .optional()?
+            .ok_or(AppError::NotFound)
+    }
+
+    fn validate_name(&self, name: &str, except: Option<i64>) -> AppResult<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("profile name is required".to_string()));
+        }
+        let taken = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM profiles WHERE name = ?1 AND id IS NOT ?2",
+                params![name, except],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if taken {
+            return Err(AppError::Validation(format!(
+                "profile name {name:?} is already used"
+            )));
+        }
+        Ok(name.to_string())
+    }
+}
+
+// Each profile's settings, cache and export history, next to the registry.
+pub fn database_file(profile_id: i64) -> String {
+    if profile_id == FIRST_PROFILE_ID {
+        "app.db".to_string()
+    } else {
+        format!("profile-{profile_id}.db")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{database_file, ProfileStore, FIRST_PROFILE_ID};
+    use crate::app_error::AppError;
+
+    fn store() -> ProfileStore {
+        let conn = rusqlite::Connection::open_in_memory().expect("open memory db");
+        let store = ProfileStore { conn };
+        store.init_schema().expect("schema");
+        store
+    }
+
+    fn names(store: &ProfileStore) -> Vec<(String, bool)> {
+        store
+            .list()
+            .expect("list")
+            .into_iter()
+            .map(|p| (p.name, p.active))
+            .collect()
+    }
+
+    #[test]
+    fn first_run_keeps_the_existing_database() {
+        let store = store();
+        store.init_schema().expect("schema again");
+
+        assert_eq!(names(&store), [("既定".to_string(), true)]);
+        assert_eq!(store.active_id().expect("active"), FIRST_PROFILE_ID);
+        assert_eq!(database_file(FIRST_PROFILE_ID), "app.db");
+        assert_eq!(database_file(2), "profile-2.db");
+    }
+
+    #[test]
+    fn profiles_can_be_created_switched_renamed_and_deleted() {
+        let store = store();
+        let client = store.create("  取引先  ").expect("create");
+        assert_eq!(client.name, "取引先");
+        assert!(matches!(
+            store.create("取引先"),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(store.create(" "), Err(AppError::Validation(_))));
+
+        store.switch(client.id).expect("switch");
+        assert_eq!(store.active_id().expect("active"), client.id);
+        store.rename(client.id, "顧客A").expect("rename");
+        store.rename(client.id, "顧客A").expect("same name");
+        assert!(matches!(
+            store.rename(client.id, "既定"),
+            Err(AppError::Validation(_))
+        ));
+        assert_eq!(
+            names(&store),
+            [("既定".to_string(), false), ("顧客A".to_string(), true)]
+        );
+
+        store.delete(client.id).expect("delete");
+        assert_eq!(store.active_id().expect("active"), FIRST_PROFILE_ID);
+        assert!(matches!(
+            store.delete(FIRST_PROFILE_ID),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(store.switch(client.id), Err(AppError::NotFound)));
+
+        let next = store.create("取引先").expect("create again");
+        assert!(next.id > client.id, "ids are not reused");
+    }
+}
@@ -0,0 +1,162 @@
+use reqwest::Url;
+
+// The API key travels in the query string, so any URL that ends up in an
+// error or a log line would carry it. Values of these parameters are
+// replaced before text leaves the client; the OAuth ones are only sent in
+// headers and form bodies, but are covered should they ever show up.
+const SECRET_PARAMS: [&str; 4] = ["apiKey", "access_token", "refresh_token", "client_secret"];
+
+pub const REDACTED: &str = "REDACTED";
+
+pub fn redact_url(url: &mut Url) {
+    if !url.query_pairs().any(|(name, _)| is_secret(&name)) {
+        return;
+    }
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_secret(&name) {
+                REDACTED.to_string()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+}
+
+// For free text such as error messages: `apiKey=<value>` becomes
+// `apiKey=REDACTED` wherever it appears. The value ends at the next query
+// separator, quote, bracket or whitespace.
+pub fn redact_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some((index, param)) = find_secret_param(rest) {
+        let value_start = index + param.len() + 1;
+        out.push_str(&rest[..value_start]);
+        out.push_str(REDACTED);
+        let value_len = rest[value_start..]
+            .find(|c: char| c.is_whitespace() || "&#\"'()<>[]{}".contains(c))
+            .unwrap_or(rest.len() - value_start);
+        rest = &rest[value_start + value_len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn find_secret_param(text: &str) -> Option<(usize, &'static str)> {
+    SECRET_PARAMS
+        .iter()
+        .filter_map(|param| {
+            text.match_indices(&format!("{param}="))
+                .map(|(index, _)| index)
+                .find(|&index| {
+                    // Only whole parameter names, not e.g. `xapiKey=`.
+                    index == 0 || matches!(text.as_bytes()[index - 1], b'?' | b'&' | b' ' | b'(')
+                })
+                .map(|index| (index, *param))
+        })
+        .min_by_key(|(index, _)| *index)
+}
+
+fn is_secret(name: &str) -> bool {
+    SECRET_PARAMS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Duration;
+
+    use reqwest::blocking::Client;
+    use reqwest::Url;
+
+    use super::{redact_text, redact_url};
+    use crate::app_error::AppError;
+
+    const KEY: &str = "s3cr3t-KEY_value";
+
+    fn client() -> Client {
+        Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .expect("client")
+    }
+
+    fn assert_redacted(error: &AppError) {
+        let message = error.to_string();
+        assert!(!message.contains(KEY), "key leaked: {message}");
+        assert!(!error.to_json().contains(KEY), "key leaked in payload");
+        assert!(message.contains("apiKey=REDACTED"), "{message}");
+    }
+
+    #[test]
+    fn redacts_keys_in_urls_and_text() {
+        let mut url = Url::parse(&format!(
+            "https://team.backlog.com/api/v2/issues?keyword=a%20b&apiKey={KEY}"
+        ))
+        .expect("url");
+        redact_url(&mut url);
+        assert_eq!(
+            url.as_str(),
+            "https://team.backlog.com/api/v2/issues?keyword=a+b&apiKey=REDACTED"
+        );
+
+        assert_eq!(
+            redact_text(&format!(
+                "error for url (https://x/api?count=1&apiKey={KEY}) and apiKey={KEY}&y=1; myapiKey=ok"
+            )),
+            "error for url (https://x/api?count=1&apiKey=REDACTED) and apiKey=REDACTED&y=1; myapiKey=ok"
+        );
+    }
+
+    #[test]
+    fn connection_errors_do_not_carry_the_key() {
+        // A port that was free a moment ago refuses the connection.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .expect("bind")
+            .local_addr()
+            .expect("addr")
+            .port();
+        let error = client()
+            .get(format!("http://127.0.0.1:{port}/api/v2/space?apiKey={KEY}"))
+            .send()
+            .expect_err("connection refused");
+
+        let error = AppError::from(error);
+        assert!(matches!(error, AppError::Network(_)));
+        assert_redacted(&error);
+    }
+
+    #[test]
+    fn status_errors_do_not_carry_the_key() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let port = listener.local_addr().expect("addr").port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            stream
+                .write_all(
+                    b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                )
+                .expect("respond");
+        });
+
+        let error = client()
+            .get(format!(
+                "http://127.0.0.1:{port}/api/v2/issues/X-1?apiKey={KEY}"
+            ))
+            .send()
+            .expect("response")
+            .error_for_status()
+            .expect_err("404");
+        server.join().expect("server");
+
+        assert_redacted(&AppError::from(error));
+    }
+}
@@ -0,0 +1,368 @@
+use std::collections::HashMap;
+
+use crate::app_error::{AppError, AppResult};
+
+// A small Mustache-style language for export files:
+//
+//   {{name}}             inserts a value
+//   {{#name}}…{{/name}}  repeats for every list item, or renders once when the
+//                        value is true or non-empty text
+//   {{^name}}…{{/name}}  renders once when the value is false, empty or an
+//                        empty list
+//   {{! note }}          is dropped
+//
+// Section and comment tags that are alone on a line remove the whole line,
+// so templates can put them on lines of their own. Values are inserted as is;
+// the output is Markdown, not HTML.
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Text(String),
+    Bool(bool),
+    List(Vec<Context>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    values: HashMap<&'static str, Value>,
+}
+
+impl Context {
+    pub fn text(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.values.insert(name, Value::Text(value.into()));
+        self
+    }
+
+    pub fn flag(mut self, name: &'static str, value: bool) -> Self {
+        self.values.insert(name, Value::Bool(value));
+        self
+    }
+
+    pub fn list(mut self, name: &'static str, items: Vec<Context>) -> Self {
+        self.values.insert(name, Value::List(items));
+        self
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Value {
+        name: String,
+        line: usize,
+    },
+    Section {
+        name: String,
+        inverted: bool,
+        line: usize,
+        children: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+struct OpenSection {
+    name: String,
+    inverted: bool,
+    line: usize,
+    outer: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> AppResult<Self> {
+        let mut nodes = Vec::new();
+        let mut open: Vec<OpenSection> = Vec::new();
+        let mut pos = 0;
+
+        while let Some(found) = source[pos..].find("{{") {
+            let start = pos + found;
+            let line = line_of(source, start);
+            let Some(length) = source[start + 2..].find("}}") else {
+                return Err(template_error(line, "`{{` is not closed"));
+            };
+            let end = start + 2 + length + 2;
+            let tag = source[start + 2..end - 2].trim();
+
+            let (sigil, name) = match tag.chars().next() {
+                Some(c @ ('#' | '^' | '/' | '!')) => (Some(c), tag[1..].trim()),
+                _ => (None, tag),
+            };
+            if sigil != Some('!') && !is_name(name) {
+                return Err(template_error(
+                    line,
+                    &format!("invalid tag `{{{{{tag}}}}}`"),
+                ));
+            }
+
+            let (text_end, next) = match sigil {
+                Some(_) => standalone_line(source, start, end).unwrap_or((start, end)),
+                None => (start, end),
+            };
+            if text_end > pos {
+                nodes.push(Node::Text(source[pos..text_end].to_string()));
+            }
+            pos = next;
+
+            match sigil {
+                None => nodes.push(Node::Value {
+                    name: name.to_string(),
+                    line,
+                }),
+                Some('!') => {}
+                Some('/') => {
+                    let Some(section) = open.pop() else {
+                        return Err(template_error(
+                            line,
+                            &format!("`{{{{/{name}}}}}` has no opening tag"),
+                        ));
+                    };
+                    if section.name != name {
+                        return Err(template_error(
+                            line,
+                            &format!(
+                                "`{{{{/{name}}}}}` closes `{}` opened on line {}",
+                                section.name, section.line
+                            ),
+                        ));
+                    }
+                    let children = std::mem::replace(&mut nodes, section.outer);
+                    nodes.push(Node::Section {
+                        name: section.name,
+                        inverted: section.inverted,
+                        line: section.line,
+                        children,
+                    });
+                }
+                Some(sigil) => open.push(OpenSection {
+                    name: name.to_string(),
+                    inverted: sigil == '^',
+                    line,
+                    outer: std::mem::take(&mut nodes),
+                }),
+            }
+        }
+
+        if let Some(section) = open.pop() {
+            return Err(template_error(
+                section.line,
+                &format!("`{}` is not closed", section.name),
+            ));
+        }
+        if pos < source.len() {
+            nodes.push(Node::Text(source[pos..].to_string()));
+        }
+        Ok(Self { nodes })
+    }
+
+    // Checks every tag, including ones inside sections that `sample` would
+    // skip, against the names `sample` provides. Lists in `sample` need at
+    // least one item so the names inside their sections can be checked.
+    pub fn validate(&self, sample: &Context) -> AppResult<()> {
+        validate_nodes(&self.nodes, &mut vec![sample])
+    }
+
+    pub fn render(&self, context: &Context) -> AppResult<String> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![context], &mut out)?;
+        Ok(out)
+    }
+}
+
+fn validate_nodes<'a>(nodes: &'a [Node], scopes: &mut Vec<&'a Context>) -> AppResult<()> {
+    for node in nodes {
+        match node {
+            Node::Text(_) => {}
+            Node::Value { name, line } => {
+                if let Value::List(_) = lookup(scopes, name, *line)? {
+                    return Err(list_as_value(name, *line));
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                line,
+                children,
+            } => match lookup(scopes, name, *line)? {
+                Value::List(items) if !inverted && !items.is_empty() => {
+                    scopes.push(&items[0]);
+                    let result = validate_nodes(children, scopes);
+                    scopes.pop();
+                    result?;
+                }
+                _ => validate_nodes(children, scopes)?,
+            },
+        }
+    }
+    Ok(())
+}
+
+fn render_nodes<'a>(
+    nodes: &'a [Node],
+    scopes: &mut Vec<&'a Context>,
+    out: &mut String,
+) -> AppResult<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { name, line } => match lookup(scopes, name, *line)? {
+                Value::Text(text) => out.push_str(text),
+                Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+                Value::List(_) => return Err(list_as_value(name, *line)),
+            },
+            Node::Section {
+                name,
+                inverted,
+                line,
+                children,
+            } => {
+                let value = lookup(scopes, name, *line)?;
+                if *inverted {
+                    let empty = match value {
+                        Value::Text(text) => text.is_empty(),
+                        Value::Bool(value) => !value,
+                        Value::List(items) => items.is_empty(),
+                    };
+                    if empty {
+                        render_nodes(children, scopes, out)?;
+                    }
+                    continue;
+                }
+                match value {
+                    Value::List(items) => {
+                        for item in items {
+                            scopes.push(item);
+                            let result = render_nodes(children, scopes, out);
+                            scopes.pop();
+                            result?;
+                        }
+                    }
+                    Value::Text(text) if !text.is_empty() => render_nodes(children, scopes, out)?,
+                    Value::Bool(true) => render_nodes(children, scopes, out)?,
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Names inside a list section see the item's values first, then the
+// enclosing ones.
+fn lookup<'a>(scopes: &[&'a Context], name: &str, line: usize) -> AppResult<&'a Value> {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.values.get(name))
+        .ok_or_else(|| template_error(line, &format!("unknown field `{name}`")))
+}
+
+fn list_as_value(name: &str, line: usize) -> AppError {
+    template_error(
+        line,
+        &format!("`{name}` is a list; use {{{{#{name}}}}}…{{{{/{name}}}}}"),
+    )
+}
+
+// When the tag at `start..end` is the only thing on its line, returns where
+// the text before the line ends and where the text after the line starts.
+fn standalone_line(source: &str, start: usize, end: usize) -> Option<(usize, usize)> {
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[end..]
+        .find('\n')
+        .map_or(source.len(), |i| end + i + 1);
+    let blank = |text: &str| text.chars().all(|c| matches!(c, ' ' | '\t' | '\r' | '\n'));
+    (blank(&source[line_start..start]) && blank(&source[end..line_end]))
+        .then_some((line_start, line_end))
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn line_of(source: &str, index: usize) -> usize {
+    source[..index].matches('\n').count() + 1
+}
+
+fn template_error(line: usize, message: &str) -> AppError {
+    AppError::Validation(format!("template line {line}: {message}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Context, Template};
+
+    fn render(source: &str, context: &Context) -> String {
+        Template::parse(source)
+            .expect("parse")
+            .render(context)
+            .expect("render")
+    }
+
+    #[test]
+    fn renders_values_sections_and_lists() {
+        let context = Context::default()
+            .text("title", "仕様")
+            .text("empty", "")
+            .flag("draft", false)
+            .list(
+                "items",
+                vec![
+                    Context::default().text("name", "a"),
+                    Context::default().text("name", "b"),
+                ],
+            );
+        let source = "# {{title}}\n{{#items}}\n- {{name}} ({{title}})\n{{/items}}\n{{^draft}}\n公開\n{{/draft}}\n{{#empty}}\nhidden\n{{/empty}}\n{{! note }}\nend";
+
+        assert_eq!(
+            render(source, &context),
+            "# 仕様\n- a (仕様)\n- b (仕様)\n公開\nend"
+        );
+    }
+
+    #[test]
+    fn inline_section_tags_keep_their_line() {
+        let context = Context::default().flag("on", true);
+        assert_eq!(render("a {{#on}}b{{/on}} c\n", &context), "a b c\n");
+    }
+
+    #[test]
+    fn reports_template_errors_with_line_numbers() {
+        let sample = Context::default()
+            .text("summary", "")
+            .list("comments", vec![Context::default().text("body", "")]);
+        let error = |source: &str| {
+            Template::parse(source)
+                .and_then(|template| template.validate(&sample))
+                .expect_err("invalid template")
+                .to_string()
+        };
+
+        assert_eq!(
+            error("ok\n{{#comments}}\n{{summary}}\n"),
+            "validation error: template line 2: `comments` is not closed"
+        );
+        assert_eq!(
+            error("{{#comments}}\n{{/summary}}"),
+            "validation error: template line 2: `{{/summary}}` closes `comments` opened on line 1"
+        );
+        assert_eq!(
+            error("{{summary}}\n{{ title }}"),
+            "validation error: template line 2: unknown field `title`"
+        );
+        assert_eq!(
+            error("{{^comments}}{{body}}{{/comments}}"),
+            "validation error: template line 1: unknown field `body`"
+        );
+        assert_eq!(
+            error("{{comments}}"),
+            "validation error: template line 1: `comments` is a list; use {{#comments}}…{{/comments}}"
+        );
+        assert_eq!(
+            error("{{summary"),
+            "validation error: template line 1: `{{` is not closed"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::{TimeZone, Utc};
+use reqwest::header::HeaderMap;
+
+use crate::models::RateLimitStatus;
+
+// Below this share of the limit, requests are spread over what is left of
+// the window instead of being sent at once.
+const RESERVE_RATIO: u64 = 5;
+const MIN_RESERVE: u64 = 5;
+
+// The budget Backlog reports with every response. `reset_at` is in Unix
+// milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Budget {
+    limit: u64,
+    remaining: u64,
+    reset_at: i64,
+}
+
+#[derive(Default)]
+struct State {
+    budget: Option<Budget>,
+    waiting_until: Option<i64>,
+}
+
+// Shared by every client of the app, so the budget carries over between
+// commands and can be shown while a long sync runs.
+#[derive(Default)]
+pub struct Throttle {
+    state: Mutex<State>,
+}
+
+impl Throttle {
+    pub fn observe(&self, headers: &HeaderMap) {
+        if let Some(budget) = parse_budget(headers) {
+            if let Ok(mut state) = self.state.lock() {
+                state.budget = Some(budget);
+            }
+        }
+    }
+
+    // Sleeps as long as the budget asks for before the next request.
+    pub fn before_request(&self) {
+        let delay = self
+            .budget()
+            .map_or(Duration::ZERO, |budget| pacing_delay(&budget, now()));
+        self.sleep(delay);
+    }
+
+    // After a 429: waits for the window to reset, or for `fallback` when
+    // Backlog did not say when that is.
+    pub fn after_rate_limit(&self, fallback: Duration) {
+        let delay = self
+            .budget()
+            .and_then(|budget| reset_delay(&budget, now()))
+            .unwrap_or(fallback);
+        log::info!("rate limited, waiting {}s", delay.as_secs());
+        self.sleep(delay);
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let Ok(state) = self.state.lock() else {
+            return RateLimitStatus::default();
+        };
+        RateLimitStatus {
+            limit: state.budget.map(|b| b.limit),
+            remaining: state.budget.map(|b| b.remaining),
+            reset_at: state.budget.and_then(|b| to_rfc3339(b.reset_at)),
+            waiting_until: state.waiting_until.and_then(to_rfc3339),
+        }
+    }
+
+    // The budget belongs to one space; another profile starts afresh.
+    pub fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.budget = None;
+        }
+    }
+
+    fn budget(&self) -> Option<Budget> {
+        self.state.lock().ok().and_then(|state| state.budget)
+    }
+
+    fn sleep(&self, delay: Duration) {
+        if delay.is_zero() {
+            return;
+        }
+        if let Ok(mut state) = self.state.lock() {
+            state.waiting_until = Some(now() + delay.as_millis() as i64);
+        }
+        thread::sleep(delay);
+        if let Ok(mut state) = self.state.lock() {
+            state.waiting_until = None;
+        }
+    }
+}
+
+fn parse_budget(headers: &HeaderMap) -> Option<Budget> {
+    let number =
+        |name: &str| -> Option<i64> { headers.get(name)?.to_str().ok()?.trim().parse().ok() };
+    Some(Budget {
+        limit: number("X-RateLimit-Limit")?.try_into().ok()?,
+        remaining: number("X-RateLimit-Remaining")?.try_into().ok()?,
+        reset_at: number("X-RateLimit-Reset")?.checked_mul(1000)?,
+    })
+}
+
+// Nothing while the budget is comfortable; then an even share of the time
+// left in the window per remaining request, so the budget lasts until the
+// reset; and the whole wait once it is used up.
+fn pacing_delay(budget: &Budget, now: i64) -> Duration {
+    let window = budget.reset_at - now;
+    if window <= 0 {
+        return Duration::ZERO;
+    }
+    let reserve = (budget.limit / RESERVE_RATIO).max(MIN_RESERVE);
+    match budget.remaining {
+        0 => millis(window),
+        remaining if remaining > reserve => Duration::ZERO,
+        remaining => millis(window / remaining as i64),
+    }
+}
+
+fn reset_delay(budget: &Budget, now: i64) -> Option<Duration> {
+    let window = budget.reset_at - now;
+    // A second of slack against clock skew between us and Backlog.
+    (window > 0).then(|| millis(window + 1000))
+}
+
+fn millis(value: i64) -> Duration {
+    Duration::from_millis(value.max(0) as u64)
+}
+
+fn now() -> i64 {
+    Utc::now().timestamp_millis()
+}
+
+fn to_rfc3339(millis: i64) -> Option<String> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .map(|time| time.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    use super::{pacing_delay, parse_budget, reset_delay, Budget};
+
+    const NOW: i64 = 1_767_225_600_000;
+
+    fn budget(remaining: u64, seconds_to_reset: i64) -> Budget {
+        Budget {
+            limit: 150,
+            remaining,
+            reset_at: NOW + seconds_to_reset * 1000,
+        }
+    }
+
+    #[test]
+    fn reads_backlog_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_budget(&headers), None);
+
+        headers.insert("X-RateLimit-Limit", HeaderValue::from_static("150"));
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("12"));
+        headers.insert("X-RateLimit-Reset", HeaderValue::from_static("1767225660"));
+        assert_eq!(
+            parse_budget(&headers),
+            Some(Budget {
+                limit: 150,
+                remaining: 12,
+                reset_at: 1_767_225_660_000,
+            })
+        );
+
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("-1"));
+        assert_eq!(parse_budget(&headers), None);
+    }
+
+    #[test]
+    fn paces_requests_once_the_budget_runs_low() {
+        assert_eq!(pacing_delay(&budget(100, 60), NOW), Duration::ZERO);
+        // 30 is the reserve of 150: 60s left are shared by 30 requests.
+        assert_eq!(pacing_delay(&budget(30, 60), NOW), Duration::from_secs(2));
+        assert_eq!(pacing_delay(&budget(0, 60), NOW), Duration::from_secs(60));
+        assert_eq!(pacing_delay(&budget(0, -5), NOW), Duration::ZERO);
+    }
+
+    #[test]
+    fn waits_past_the_reset_after_a_429() {
+        assert_eq!(
+            reset_delay(&budget(0, 20), NOW),
+            Some(Duration::from_secs(21))
+        );
+        assert_eq!(reset_delay(&budget(0, 0), NOW), None);
+    }
+}
@@ -6,21 +6,163 @@ import {
   clearExports,
   exportsList,
   issueExportMarkdown,
+  issuesExportBulk,
+  issuesSearch,
+  searchFilterOptions,
   issueGetDetail,
+  issuesConversionReport,
+  markdownToBacklog,
+  profileCreate,
+  profileDelete,
+  profileRename,
+  profileSwitch,
+  profilesList,
   projectsSync,
+  rateLimitStatus,
   searchByKey,
   searchByKeyword,
+  exportTemplateGet,
   setExportDir,
+  setExportTemplate,
+  setFrontMatter,
   setupLoad,
-  setupSave
+  setupSave,
+  wikiGet,
+  wikisExport,
+  wikisList
 } from './api';
-import type { ExportHistory, IssueDetail, IssueSummary, Project } from './types';
+import type {
+  AuthMethod,
+  BulkExportResult,
+  BulkExportTarget,
+  ConversionReport,
+  ExportHistory,
+  FilterOptions,
+  IssueFilter,
+  IssueSearchPage,
+  FrontMatterField,
+  FrontMatterSettings,
+  IssueDetail,
+  IssueSummary,
+  Profile,
+  Project,
+  RateLimitStatus,
+  WikiExportResult,
+  WikiPage,
+  WikiSummary
+} from './types';
 
 const EXPORT_HISTORY_LIMIT = 200;
+const SEARCH_PAGE_SIZE = 20;
+const SEARCH_MAX_PAGE_SIZE = 100;
+const RATE_LIMIT_POLL_MS = 1000;
+// Backlog compares dates in the space's time zone; the cache only has UTC
+// timestamps, so offline results can differ for issues near midnight.
+const DATE_FILTER_HINT =
+  'Backlogはスペースのタイムゾーンで日付を判定します。オフライン時のキャッシュ検索はUTCの日付で判定するため、日付の変わり目付近の課題は結果が異なることがあります。';
+
+type SearchMode = 'key' | 'keyword' | 'filter';
+
+// Select values are strings; an empty one means the condition is not used.
+type FilterForm = {
+  projectId: string;
+  statusId: string;
+  assigneeId: string;
+  issueTypeId: string;
+  categoryId: string;
+  milestoneId: string;
+  createdSince: string;
+  createdUntil: string;
+  updatedSince: string;
+  updatedUntil: string;
+  sort: 'created' | 'updated' | 'dueDate';
+  order: 'asc' | 'desc';
+};
+
+const EMPTY_FILTER_FORM: FilterForm = {
+  projectId: '',
+  statusId: '',
+  assigneeId: '',
+  issueTypeId: '',
+  categoryId: '',
+  milestoneId: '',
+  createdSince: '',
+  createdUntil: '',
+  updatedSince: '',
+  updatedUntil: '',
+  sort: 'updated',
+  order: 'desc'
+};
+
+function toIssueFilter(form: FilterForm, keyword: string): IssueFilter {
+  const ids = (value: string) => (value ? [Number(value)] : []);
+  return {
+    keyword: keyword.trim() || null,
+    projectIds: ids(form.projectId),
+    statusIds: ids(form.statusId),
+    assigneeIds: ids(form.assigneeId),
+    issueTypeIds: ids(form.issueTypeId),
+    categoryIds: ids(form.categoryId),
+    milestoneIds: ids(form.milestoneId),
+    createdSince: form.createdSince || null,
+    createdUntil: form.createdUntil || null,
+    updatedSince: form.updatedSince || null,
+    updatedUntil: form.updatedUntil || null,
+    sort: form.sort,
+    order: form.order
+  };
+}
+
+const FRONT_MATTER_FIELDS: [FrontMatterField, string][] = [
+  ['issue_key', '課題キー'],
+  ['summary', '件名'],
+  ['status', '状態'],
+  ['assignee', '担当者'],
+  ['url', 'URL'],
+  ['updated_at', '更新日時'],
+  ['exported_at', 'エクスポート日時'],
+  ['tags', 'タグ (カテゴリー)']
+];
+
+function issueMetadata(issue: IssueDetail): [string, string][] {
+  const fields: [string, string | null][] = [
+    ['種別', issue.issueType],
+    ['状態', issue.status],
+    ['優先度', issue.priority],
+    ['担当者', issue.assignee],
+    ['登録者', issue.createdUser],
+    ['期限日', issue.dueDate ? new Date(issue.dueDate).toLocaleDateString() : null],
+    ['親課題', issue.parentIssueKey],
+    ['カテゴリー', issue.categories.join(', ')],
+    ['発生バージョン', issue.versions.join(', ')],
+    ['マイルストーン', issue.milestones.join(', ')]
+  ];
+  return fields.filter((field): field is [string, string] => !!field[1]);
+}
+
+function rateLimitNote(status: RateLimitStatus | null): string | null {
+  if (!status) {
+    return null;
+  }
+  if (status.waitingUntil) {
+    return `APIの利用上限に近いため ${new Date(status.waitingUntil).toLocaleTimeString()} まで待機しています`;
+  }
+  if (status.remaining === undefined || status.limit === undefined) {
+    return null;
+  }
+  const reset = status.resetAt ? `（${new Date(status.resetAt).toLocaleTimeString()} にリセット）` : '';
+  return `API残り ${status.remaining}/${status.limit}${reset}`;
+}
 
 export function App() {
+  const [profiles, setProfiles] = useState<Profile[]>([]);
+  const [profileName, setProfileName] = useState('');
   const [spaceUrl, setSpaceUrl] = useState('');
   const [apiKey, setApiKey] = useState('');
+  const [authMethod, setAuthMethod] = useState<AuthMethod>('apiKey');
+  const [clientId, setClientId] = useState('');
+  const [clientSecret, setClientSecret] = useState('');
+  const [oauthRedirectUri, setOauthRedirectUri] = useState('');
   const [exportDir, setExportDirState] = useState('');
   const [isConfigured, setIsConfigured] = useState(false);
 
@@ -28,17 +170,38 @@ export function App() {
   const [issues, setIssues] = useState<IssueSummary[]>([]);
   const [selectedIssue, setSelectedIssue] = useState<IssueDetail | null>(null);
   const [exports, setExports] = useState<ExportHistory[]>([]);
+  const [bulkResult, setBulkResult] = useState<BulkExportResult | null>(null);
+  const [conversionReports, setConversionReports] = useState<ConversionReport[] | null>(null);
 
+  const [wikiProjectKey, setWikiProjectKey] = useState('');
+  const [wikis, setWikis] = useState<WikiSummary[] | null>(null);
+  const [selectedWiki, setSelectedWiki] = useState<WikiPage | null>(null);
+  const [wikiResult, setWikiResult] = useState<WikiExportResult | null>(null);
   const [query, setQuery] = useState('');
-  const [searchMode, setSearchMode] = useState<'key' | 'keyword'>('keyword');
+  const [searchMode, setSearchMode] = useState<SearchMode>('keyword');
+  const [filterForm, setFilterForm] = useState<FilterForm>(EMPTY_FILTER_FORM);
+  const [filterOptions, setFilterOptions] = useState<FilterOptions | null>(null);
   const [error, setError] = useState<string | null>(null);
   const [notice, setNotice] = useState<string | null>(null);
   const [exportError, setExportError] = useState<string | null>(null);
   const [exportNotice, setExportNotice] = useState<string | null>(null);
   const [loading, setLoading] = useState(false);
+  const [rateLimit, setRateLimit] = useState<RateLimitStatus | null>(null);
   const [hasSearched, setHasSearched] = useState(false);
+  const [searchTotal, setSearchTotal] = useState<number | null>(null);
+  const [linkIssueFiles, setLinkIssueFiles] = useState(false);
+  const [exportTemplate, setExportTemplateState] = useState('');
+  const [templateIsDefault, setTemplateIsDefault] = useState(true);
+  const [frontMatter, setFrontMatterState] = useState<FrontMatterSettings>({
+    enabled: true,
+    fields: FRONT_MATTER_FIELDS.map(([field]) => field)
+  });
+  const [draftMarkdown, setDraftMarkdown] = useState('');
+  const [draftProjectKey, setDraftProjectKey] = useState('');
+  const [draftBacklog, setDraftBacklog] = useState('');
 
-  const canSearch = isConfigured && query.trim().length > 0;
+  const canSearch = isConfigured && (searchMode === 'filter' || query.trim().length > 0);
+  const rateLimitMessage = rateLimitNote(rateLimit);
 
   function mapErrorMessage(raw: string) {
     let message = raw;
@@ -47,7 +210,7 @@ export function App() {
     }
     if (raw.includes('[KEYCHAIN]')) {
       message =
-        'APIキーをKeychainから取得できません。初期設定で再保存し、macOSのキーチェーンアクセス許可を確認してください。';
+        '認証情報をKeychainから取得できません。初期設定で再保存し、macOSのキーチェーンアクセス許可を確認してください。';
     }
     return message;
   }
@@ -64,23 +227,71 @@ export function App() {
     setError(message);
   }
 
+  // Settings, credentials, cache and history all belong to the active
+  // profile, so everything shown is reloaded when it changes.
+  async function loadActiveProfile() {
+    setProfiles(await profilesList());
+    setApiKey('');
+    setClientId('');
+    setClientSecret('');
+    setProjects([]);
+    setIssues([]);
+    setSelectedIssue(null);
+    setExports([]);
+    setBulkResult(null);
+    setConversionReports(null);
+    setWikiProjectKey('');
+    setWikis(null);
+    setSelectedWiki(null);
+    setWikiResult(null);
+    setFilterForm(EMPTY_FILTER_FORM);
+    setFilterOptions(null);
+    setHasSearched(false);
+    setSearchTotal(null);
+    setRateLimit(null);
+
+    const setup = await setupLoad();
+    setSpaceUrl(setup.spaceUrl ?? '');
+    setAuthMethod(setup.authMethod);
+    setOauthRedirectUri(setup.oauthRedirectUri);
+    setExportDirState(setup.exportDir ?? '');
+    setFrontMatterState(setup.frontMatter);
+    const template = await exportTemplateGet();
+    setExportTemplateState(template.template);
+    setTemplateIsDefault(template.isDefault);
+    setIsConfigured(setup.hasApiKey && Boolean(setup.spaceUrl));
+    if (setup.hasApiKey && setup.spaceUrl) {
+      const history = await exportsList(EXPORT_HISTORY_LIMIT);
+      setExports(history);
+    }
+  }
+
   useEffect(() => {
     void (async () => {
       try {
-        const setup = await setupLoad();
-        setSpaceUrl(setup.spaceUrl ?? '');
-        setExportDirState(setup.exportDir ?? '');
-        setIsConfigured(setup.hasApiKey && Boolean(setup.spaceUrl));
-        if (setup.hasApiKey && setup.spaceUrl) {
-          const history = await exportsList(EXPORT_HISTORY_LIMIT);
-          setExports(history);
-        }
+        await loadActiveProfile();
       } catch (e) {
         handleAppError(e);
       }
     })();
   }, []);
 
+  // Long syncs and exports can be held back by Backlog's rate limit; the
+  // budget is polled meanwhile so the status line can say so.
+  useEffect(() => {
+    const refresh = () => {
+      rateLimitStatus()
+        .then(setRateLimit)
+        .catch(() => undefined);
+    };
+    refresh();
+    if (!loading) {
+      return undefined;
+    }
+    const timer = window.setInterval(refresh, RATE_LIMIT_POLL_MS);
+    return () => window.clearInterval(timer);
+  }, [loading]);
+
   const selectedUpdated = useMemo(
     () => (selectedIssue ? new Date(selectedIssue.updatedAt).toLocaleString() : '-'),
     [selectedIssue]
@@ -90,9 +301,17 @@ export function App() {
     setLoading(true);
     setError(null);
     setNotice(null);
+    if (authMethod === 'oauth') {
+      setNotice('ブラウザでBacklogにログインし、アクセスを許可してください。');
+    }
     try {
-      await setupSave(spaceUrl, apiKey);
+      await setupSave(
+        spaceUrl,
+        authMethod === 'oauth' ? { method: 'oauth', clientId, clientSecret } : { method: 'apiKey', apiKey }
+      );
+      setNotice(null);
       setApiKey('');
+      setClientSecret('');
       setIsConfigured(true);
       const synced = await projectsSync();
       setProjects(synced);
@@ -127,9 +346,16 @@ export function App() {
     setIssues([]);
     setSelectedIssue(null);
     setHasSearched(false);
+    setSearchTotal(null);
     try {
-      const found = searchMode === 'key' ? await searchByKey(query) : await searchByKeyword(query);
-      setIssues(found);
+      if (searchMode === 'key') {
+        setIssues(await searchByKey(query));
+      } else {
+        const page = await fetchSearchPage(SEARCH_PAGE_SIZE, 0);
+        setIssues(page.issues);
+        setSearchTotal(page.total ?? page.issues.length);
+      }
+      setConversionReports(null);
       setHasSearched(true);
     } catch (e) {
       handleAppError(e);
@@ -138,6 +364,83 @@ export function App() {
     }
   }
 
+  function fetchSearchPage(count: number, offset: number): Promise<IssueSearchPage> {
+    return searchMode === 'filter'
+      ? issuesSearch(toIssueFilter(filterForm, query), count, offset)
+      : searchByKeyword(query, count, offset);
+  }
+
+  // Statuses, users and the other choices belong to a project, so they are
+  // reloaded and the dependent conditions cleared when the project changes.
+  async function handleFilterProjectChange(projectId: string) {
+    setFilterForm({
+      ...filterForm,
+      projectId,
+      statusId: '',
+      assigneeId: '',
+      issueTypeId: '',
+      categoryId: '',
+      milestoneId: ''
+    });
+    setFilterOptions(null);
+    if (!projectId) return;
+    try {
+      setFilterOptions(await searchFilterOptions(Number(projectId)));
+    } catch (e) {
+      handleAppError(e);
+    }
+  }
+
+  function updateFilter(changes: Partial<FilterForm>) {
+    setFilterForm({ ...filterForm, ...changes });
+  }
+
+  // Pages keep being fetched after the current results until `total` is
+  // reached, or only once unless `all` is set. The total is the one from the
+  // first page; later pages do not count the hits again.
+  async function handleLoadMore(all: boolean) {
+    if (searchTotal === null) return;
+    setLoading(true);
+    setError(null);
+    let loaded = issues;
+    try {
+      while (loaded.length < searchTotal) {
+        const page = await fetchSearchPage(all ? SEARCH_MAX_PAGE_SIZE : SEARCH_PAGE_SIZE, loaded.length);
+        if (page.issues.length === 0) break;
+        loaded = [...loaded, ...page.issues];
+        setIssues(loaded);
+        if (!all) break;
+      }
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleConversionCheck() {
+    setLoading(true);
+    setError(null);
+    setNotice(null);
+    try {
+      const reports = await issuesConversionReport(issues.map((i) => i.issueKey));
+      setConversionReports(reports);
+      setNotice(
+        reports.length === 0
+          ? '変換で失われる書式はありません。'
+          : `${reports.length}件の課題で変換時に書式が失われます。`
+      );
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  function warningCount(issueKey: string) {
+    return conversionReports?.find((r) => r.issueKey === issueKey)?.warnings.length ?? 0;
+  }
+
   async function handleSelectIssue(issueKey: string) {
     setLoading(true);
     setError(null);
@@ -164,8 +467,9 @@ export function App() {
     }
 
     try {
-      const path = await issueExportMarkdown(selectedIssue.issueKey, exportDir, false);
-      setExportNotice(`エクスポートしました: ${path}`);
+      const result = await issueExportMarkdown(selectedIssue.issueKey, exportDir, false, linkIssueFiles);
+      const attachments = result.attachmentCount > 0 ? `（添付ファイル ${result.attachmentCount}件）` : '';
+      setExportNotice(`エクスポートしました: ${result.path}${attachments}`);
     } catch (e) {
       const raw = (e as Error).message;
       const message = mapErrorMessage(raw);
@@ -183,6 +487,96 @@ export function App() {
     setExports(history);
   }
 
+  async function handleBulkExport(target: BulkExportTarget, label: string) {
+    setError(null);
+    setNotice(null);
+    setBulkResult(null);
+    if (!exportDir.trim()) {
+      setError('先に保存先ディレクトリを設定してください。');
+      return;
+    }
+    const yes = await ask(`${label}を一括エクスポートします。課題数によっては時間がかかります。続行しますか？`, {
+      title: '確認',
+      type: 'info'
+    });
+    if (!yes) return;
+
+    setLoading(true);
+    try {
+      const result = await issuesExportBulk(target, exportDir, false, linkIssueFiles);
+      setBulkResult(result);
+      setNotice(`${result.exported}件をエクスポートしました。` + (result.failed > 0 ? `${result.failed}件は失敗しました。` : ''));
+      setExports(await exportsList(EXPORT_HISTORY_LIMIT));
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleListWikis(projectKey: string) {
+    setWikiProjectKey(projectKey);
+    setWikis(null);
+    setSelectedWiki(null);
+    setWikiResult(null);
+    if (!projectKey) return;
+    setError(null);
+    setLoading(true);
+    try {
+      setWikis(await wikisList(projectKey));
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleSelectWiki(wikiId: number) {
+    setError(null);
+    setLoading(true);
+    try {
+      setSelectedWiki(await wikiGet(wikiId));
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  // An empty id list exports every page of the project.
+  async function handleWikiExport(wikiIds: number[]) {
+    setError(null);
+    setNotice(null);
+    setWikiResult(null);
+    if (!exportDir.trim()) {
+      setError('先に保存先ディレクトリを設定してください。');
+      return;
+    }
+
+    setLoading(true);
+    try {
+      const result = await wikisExport(wikiProjectKey, wikiIds, exportDir, false);
+      setWikiResult(result);
+      setNotice(
+        `Wikiを${result.exported}ページエクスポートしました。` + (result.failed > 0 ? `${result.failed}ページは失敗しました。` : '')
+      );
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleConvertDraft() {
+    setError(null);
+    setNotice(null);
+    try {
+      setDraftBacklog(await markdownToBacklog(draftMarkdown, draftProjectKey || undefined));
+    } catch (e) {
+      handleAppError(e);
+    }
+  }
+
   async function handleAuthReset() {
     const yes = await ask('保存済みの認証情報（APIキー・Space URL）を削除します。続行しますか？', {
       title: '確認',
@@ -210,6 +604,73 @@ export function App() {
     }
   }
 
+  async function handleProfileSwitch(profileId: number) {
+    setLoading(true);
+    setError(null);
+    setNotice(null);
+    try {
+      await profileSwitch(profileId);
+      await loadActiveProfile();
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleProfileCreate() {
+    setLoading(true);
+    setError(null);
+    setNotice(null);
+    try {
+      const created = await profileCreate(profileName);
+      await profileSwitch(created.id);
+      await loadActiveProfile();
+      setProfileName('');
+      setNotice(`プロファイル「${created.name}」を作成しました。初期設定を行ってください。`);
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  async function handleProfileRename() {
+    const active = profiles.find((p) => p.active);
+    if (!active) return;
+    setError(null);
+    setNotice(null);
+    try {
+      await profileRename(active.id, profileName);
+      setProfiles(await profilesList());
+      setProfileName('');
+    } catch (e) {
+      handleAppError(e);
+    }
+  }
+
+  async function handleProfileDelete() {
+    const active = profiles.find((p) => p.active);
+    if (!active) return;
+    const yes = await ask(
+      `プロファイル「${active.name}」を削除します。保存済みの認証情報・設定・キャッシュも削除されます。続行しますか？`,
+      { title: '確認', type: 'warning' }
+    );
+    if (!yes) return;
+
+    setLoading(true);
+    setError(null);
+    setNotice(null);
+    try {
+      await profileDelete(active.id);
+      await loadActiveProfile();
+    } catch (e) {
+      handleAppError(e);
+    } finally {
+      setLoading(false);
+    }
+  }
+
   async function handlePickExportDir() {
     setError(null);
     setNotice(null);
@@ -225,6 +686,37 @@ export function App() {
     }
   }
 
+  async function handleFrontMatterChange(next: FrontMatterSettings) {
+    setExportError(null);
+    try {
+      await setFrontMatter(next);
+      setFrontMatterState(next);
+    } catch (e) {
+      setExportError(e instanceof Error ? e.message : String(e));
+    }
+  }
+
+  async function handleSaveTemplate(template: string | null) {
+    setExportError(null);
+    setExportNotice(null);
+    try {
+      await setExportTemplate(template);
+      const saved = await exportTemplateGet();
+      setExportTemplateState(saved.template);
+      setTemplateIsDefault(saved.isDefault);
+      setExportNotice(template === null ? 'テンプレートを既定に戻しました。' : 'テンプレートを保存しました。');
+    } catch (e) {
+      setExportError(e instanceof Error ? e.message : String(e));
+    }
+  }
+
+  function toggleFrontMatterField(field: FrontMatterField, checked: boolean) {
+    const fields = checked
+      ? [...frontMatter.fields, field]
+      : frontMatter.fields.filter((f) => f !== field);
+    void handleFrontMatterChange({ ...frontMatter, fields });
+  }
+
   async function handleClearExports() {
     const yes = await ask('エクスポート履歴をすべて削除します。続行しますか？', {
       title: '確認',
@@ -251,6 +743,38 @@ export function App() {
       <header className="app-header">
         <h1>Backlog Markdown Exporter</h1>
         <p>Backlog課題をMarkdownとしてローカル保存するmacOS向けツール</p>
+        <div className="row gap profile-row">
+          <select
+            aria-label="プロファイル"
+            value={profiles.find((p) => p.active)?.id ?? ''}
+            onChange={(e) => void handleProfileSwitch(Number(e.target.value))}
+            disabled={loading}
+          >
+            {profiles.map((p) => (
+              <option key={p.id} value={p.id}>
+                {p.name}
+              </option>
+            ))}
+          </select>
+          <input
+            placeholder="プロファイル名"
+            value={profileName}
+            onChange={(e) => setProfileName(e.target.value)}
+          />
+          <button disabled={loading || !profileName.trim()} onClick={() => void handleProfileCreate()}>
+            新規作成
+          </button>
+          <button disabled={loading || !profileName.trim()} onClick={() => void handleProfileRename()}>
+            名前を変更
+          </button>
+          <button
+            className="danger-button"
+            disabled={loading || profiles.length <= 1}
+            onClick={() => void handleProfileDelete()}
+          >
+            削除
+          </button>
+        </div>
       </header>
 
       {!isConfigured && (
@@ -265,16 +789,44 @@ export function App() {
             />
           </label>
           <label>
-            APIキー
-            <input
-              placeholder="Backlog API Key"
-              type="password"
-              value={apiKey}
-              onChange={(e) => setApiKey(e.target.value)}
-            />
+            認証方式
+            <select value={authMethod} onChange={(e) => setAuthMethod(e.target.value as AuthMethod)}>
+              <option value="apiKey">APIキー</option>
+              <option value="oauth">OAuth 2.0</option>
+            </select>
           </label>
-          <button disabled={loading || !spaceUrl || !apiKey} onClick={handleSetupSave}>
-            保存して接続テスト
+          {authMethod === 'apiKey' ? (
+            <label>
+              APIキー
+              <input
+                placeholder="Backlog API Key"
+                type="password"
+                value={apiKey}
+                onChange={(e) => setApiKey(e.target.value)}
+              />
+            </label>
+          ) : (
+            <>
+              <label>
+                Client ID
+                <input value={clientId} onChange={(e) => setClientId(e.target.value)} />
+              </label>
+              <label>
+                Client Secret
+                <input type="password" value={clientSecret} onChange={(e) => setClientSecret(e.target.value)} />
+              </label>
+              <p className="subtle">
+                Backlog Developerで登録したアプリのリダイレクトURIに <code>{oauthRedirectUri}</code> を設定してください。
+              </p>
+            </>
+          )}
+          <button
+            disabled={
+              loading || !spaceUrl || (authMethod === 'apiKey' ? !apiKey : !clientId.trim() || !clientSecret.trim())
+            }
+            onClick={handleSetupSave}
+          >
+            {authMethod === 'oauth' ? 'ブラウザで認証して接続テスト' : '保存して接続テスト'}
           </button>
         </section>
       )}
@@ -298,8 +850,18 @@ export function App() {
             ) : (
               <ul className="project-list">
                 {projects.map((p) => (
-                  <li key={p.id}>
-                    <strong>{p.projectKey}</strong> {p.name}
+                  <li key={p.id} className="row between">
+                    <span>
+                      <strong>{p.projectKey}</strong> {p.name}
+                    </span>
+                    <button
+                      disabled={loading}
+                      onClick={() =>
+                        void handleBulkExport({ kind: 'project', projectKey: p.projectKey }, `${p.projectKey}の全課題`)
+                      }
+                    >
+                      一括エクスポート
+                    </button>
                   </li>
                 ))}
               </ul>
@@ -309,9 +871,10 @@ export function App() {
           <section className="panel">
             <h2>課題検索</h2>
             <div className="row gap search-controls">
-              <select value={searchMode} onChange={(e) => setSearchMode(e.target.value as 'key' | 'keyword')}>
+              <select value={searchMode} onChange={(e) => setSearchMode(e.target.value as SearchMode)}>
                 <option value="keyword">キーワード</option>
                 <option value="key">課題キー</option>
+                <option value="filter">詳細検索</option>
               </select>
               <input
                 value={query}
@@ -321,18 +884,115 @@ export function App() {
                     void handleSearch();
                   }
                 }}
-                placeholder={searchMode === 'key' ? 'PROJ-123' : '検索キーワード'}
+                placeholder={
+                  searchMode === 'key' ? 'PROJ-123' : searchMode === 'filter' ? 'キーワード (任意)' : '検索キーワード'
+                }
               />
               <button disabled={!canSearch || loading} onClick={handleSearch}>
                 検索
               </button>
+              <button disabled={issues.length === 0 || loading} onClick={handleConversionCheck}>
+                変換チェック
+              </button>
+              <button
+                disabled={issues.length === 0 || loading}
+                onClick={() =>
+                  void handleBulkExport(
+                    { kind: 'keys', issueKeys: issues.map((i) => i.issueKey) },
+                    `検索結果の${issues.length}件`
+                  )
+                }
+              >
+                一括エクスポート
+              </button>
             </div>
+            {searchMode === 'filter' && (
+              <div className="search-filters">
+                <label>
+                  プロジェクト
+                  <select value={filterForm.projectId} onChange={(e) => void handleFilterProjectChange(e.target.value)}>
+                    <option value="">すべて</option>
+                    {projects.map((p) => (
+                      <option key={p.id} value={p.id}>
+                        {p.projectKey}
+                      </option>
+                    ))}
+                  </select>
+                </label>
+                {(
+                  [
+                    ['状態', 'statusId', filterOptions?.statuses],
+                    ['担当者', 'assigneeId', filterOptions?.assignees],
+                    ['種別', 'issueTypeId', filterOptions?.issueTypes],
+                    ['カテゴリー', 'categoryId', filterOptions?.categories],
+                    ['マイルストーン', 'milestoneId', filterOptions?.milestones]
+                  ] as const
+                ).map(([label, field, options]) => (
+                  <label key={field}>
+                    {label}
+                    <select
+                      value={filterForm[field]}
+                      disabled={!options}
+                      onChange={(e) => updateFilter({ [field]: e.target.value } as Partial<FilterForm>)}
+                    >
+                      <option value="">{options ? 'すべて' : 'プロジェクトを選択'}</option>
+                      {options?.map((o) => (
+                        <option key={o.id} value={o.id}>
+                          {o.name}
+                        </option>
+                      ))}
+                    </select>
+                  </label>
+                ))}
+                {(
+                  [
+                    ['登録日 (から)', 'createdSince'],
+                    ['登録日 (まで)', 'createdUntil'],
+                    ['更新日 (から)', 'updatedSince'],
+                    ['更新日 (まで)', 'updatedUntil']
+                  ] as const
+                ).map(([label, field]) => (
+                  <label key={field} title={DATE_FILTER_HINT}>
+                    {label}
+                    <input
+                      type="date"
+                      value={filterForm[field]}
+                      onChange={(e) => updateFilter({ [field]: e.target.value } as Partial<FilterForm>)}
+                    />
+                  </label>
+                ))}
+                <label>
+                  並び順
+                  <select
+                    value={filterForm.sort}
+                    onChange={(e) => updateFilter({ sort: e.target.value as FilterForm['sort'] })}
+                  >
+                    <option value="updated">更新日時</option>
+                    <option value="created">登録日時</option>
+                    <option value="dueDate">期限日</option>
+                  </select>
+                </label>
+                <label>
+                  順序
+                  <select
+                    value={filterForm.order}
+                    onChange={(e) => updateFilter({ order: e.target.value as FilterForm['order'] })}
+                  >
+                    <option value="desc">降順</option>
+                    <option value="asc">昇順</option>
+                  </select>
+                </label>
+              </div>
+            )}
             <ul className="issue-list">
               {issues.map((i) => (
                 <li key={i.issueKey}>
                   <button className="issue-select-button" onClick={() => handleSelectIssue(i.issueKey)}>
                     <span>
                       <strong>{i.issueKey}</strong>: {i.summary}
+                      {warningCount(i.issueKey) > 0 && (
+                        <span className="warning-badge">変換注意 {warningCount(i.issueKey)}件</span>
+                      )}
                     </span>
                     <span className="issue-select-hint">クリックして詳細表示</span>
                   </button>
@@ -340,6 +1000,37 @@ export function App() {
               ))}
             </ul>
             {hasSearched && issues.length === 0 && <p className="subtle">検索結果が見つかりませんでした。</p>}
+            {searchTotal !== null && issues.length > 0 && (
+              <div className="row gap search-paging">
+                <span className="subtle">
+                  {searchTotal}件中 {issues.length}件を表示
+                </span>
+                {issues.length < searchTotal && (
+                  <>
+                    <button disabled={loading} onClick={() => void handleLoadMore(false)}>
+                      さらに読み込む
+                    </button>
+                    <button disabled={loading} onClick={() => void handleLoadMore(true)}>
+                      すべて読み込む
+                    </button>
+                  </>
+                )}
+              </div>
+            )}
+            {bulkResult && bulkResult.failed > 0 && (
+              <div className="bulk-failures">
+                <p className="subtle">エクスポートに失敗した課題 ({bulkResult.failed}件)</p>
+                <ul>
+                  {bulkResult.items
+                    .filter((item) => item.error)
+                    .map((item) => (
+                      <li key={item.issueKey}>
+                        <strong>{item.issueKey}</strong>: {item.error}
+                      </li>
+                    ))}
+                </ul>
+              </div>
+            )}
           </section>
 
           <section className="panel">
@@ -360,20 +1051,175 @@ export function App() {
                   {selectedIssue.issueKey}: {selectedIssue.summary}
                 </h3>
                 <p className="subtle">更新日時: {selectedUpdated}</p>
+                <dl className="issue-metadata">
+                  {issueMetadata(selectedIssue).map(([label, value]) => (
+                    <div key={label}>
+                      <dt>{label}</dt>
+                      <dd>{value}</dd>
+                    </div>
+                  ))}
+                </dl>
                 <article className="preview">
                   <ReactMarkdown>{selectedIssue.descriptionMd || '(本文なし)'}</ReactMarkdown>
                 </article>
+                {selectedIssue.conversionWarnings.length > 0 && (
+                  <div className="conversion-warnings">
+                    <p className="subtle">変換時の注意 ({selectedIssue.conversionWarnings.length}件)</p>
+                    <ul>
+                      {selectedIssue.conversionWarnings.map((w, index) => (
+                        <li key={index}>
+                          {w.line}行目 <code>{w.construct}</code>: {w.action}
+                        </li>
+                      ))}
+                    </ul>
+                  </div>
+                )}
                 <div className="row export-action-row">
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={linkIssueFiles}
+                      onChange={(e) => setLinkIssueFiles(e.target.checked)}
+                    />
+                    課題キーを保存先の.mdファイルへリンク
+                  </label>
                   <button disabled={!selectedIssue} onClick={handleExport}>
                     Markdownエクスポート
                   </button>
                 </div>
+                <details className="front-matter-settings">
+                  <summary>フロントマター (YAML)</summary>
+                  <label className="checkbox-label">
+                    <input
+                      type="checkbox"
+                      checked={frontMatter.enabled}
+                      onChange={(e) => void handleFrontMatterChange({ ...frontMatter, enabled: e.target.checked })}
+                    />
+                    エクスポートしたファイルの先頭に付ける
+                  </label>
+                  <div className="front-matter-fields">
+                    {FRONT_MATTER_FIELDS.map(([field, label]) => (
+                      <label key={field} className="checkbox-label">
+                        <input
+                          type="checkbox"
+                          disabled={!frontMatter.enabled}
+                          checked={frontMatter.fields.includes(field)}
+                          onChange={(e) => toggleFrontMatterField(field, e.target.checked)}
+                        />
+                        {label}
+                      </label>
+                    ))}
+                  </div>
+                </details>
+                <details className="export-template-settings">
+                  <summary>エクスポートテンプレート{templateIsDefault ? ' (既定)' : ''}</summary>
+                  <p className="subtle">
+                    {'{{summary}}'} などで課題の項目、{'{{#comments}}…{{/comments}}'} でコメントを繰り返し出力します。
+                  </p>
+                  <textarea
+                    value={exportTemplate}
+                    onChange={(e) => setExportTemplateState(e.target.value)}
+                    spellCheck={false}
+                  />
+                  <div className="row gap">
+                    <button onClick={() => void handleSaveTemplate(exportTemplate)}>テンプレートを保存</button>
+                    <button disabled={templateIsDefault} onClick={() => void handleSaveTemplate(null)}>
+                      既定に戻す
+                    </button>
+                  </div>
+                </details>
                 {exportNotice && <p className="status export-feedback">{exportNotice}</p>}
                 {exportError && <p className="error export-feedback">{exportError}</p>}
               </>
             )}
           </section>
 
+          <section className="panel">
+            <div className="row between">
+              <h2>Wiki</h2>
+              <div className="row gap">
+                <select value={wikiProjectKey} onChange={(e) => void handleListWikis(e.target.value)}>
+                  <option value="">プロジェクトを選択</option>
+                  {projects.map((p) => (
+                    <option key={p.id} value={p.projectKey}>
+                      {p.projectKey}
+                    </option>
+                  ))}
+                </select>
+                <button disabled={loading || !wikis || wikis.length === 0} onClick={() => void handleWikiExport([])}>
+                  すべてエクスポート
+                </button>
+              </div>
+            </div>
+            {wikis && wikis.length === 0 && <p className="subtle">Wikiページはありません。</p>}
+            {wikis && wikis.length > 0 && (
+              <ul className="issue-list wiki-list">
+                {wikis.map((w) => (
+                  <li key={w.id}>
+                    <button className="issue-select-button" onClick={() => void handleSelectWiki(w.id)}>
+                      <span>{w.name}</span>
+                      <span className="issue-select-hint">{new Date(w.updatedAt).toLocaleString()}</span>
+                    </button>
+                  </li>
+                ))}
+              </ul>
+            )}
+            {selectedWiki && (
+              <>
+                <div className="row between">
+                  <h3>{selectedWiki.name}</h3>
+                  <button disabled={loading} onClick={() => void handleWikiExport([selectedWiki.id])}>
+                    このページをエクスポート
+                  </button>
+                </div>
+                <article className="preview">
+                  <ReactMarkdown>{selectedWiki.contentMd || '(本文なし)'}</ReactMarkdown>
+                </article>
+              </>
+            )}
+            {wikiResult && wikiResult.failed > 0 && (
+              <div className="bulk-failures">
+                <p className="subtle">エクスポートに失敗したページ ({wikiResult.failed}件)</p>
+                <ul>
+                  {wikiResult.items
+                    .filter((item) => item.error)
+                    .map((item) => (
+                      <li key={item.wikiId}>
+                        <strong>{item.name}</strong>: {item.error}
+                      </li>
+                    ))}
+                </ul>
+              </div>
+            )}
+          </section>
+
+          <section className="panel">
+            <div className="row between">
+              <h2>Markdown → Backlog記法</h2>
+              <div className="row gap">
+                <select value={draftProjectKey} onChange={(e) => setDraftProjectKey(e.target.value)}>
+                  <option value="">プロジェクト指定なし</option>
+                  {projects.map((p) => (
+                    <option key={p.id} value={p.projectKey}>
+                      {p.projectKey}
+                    </option>
+                  ))}
+                </select>
+                <button disabled={!draftMarkdown.trim()} onClick={handleConvertDraft}>
+                  変換
+                </button>
+              </div>
+            </div>
+            <div className="draft-columns">
+              <textarea
+                value={draftMarkdown}
+                onChange={(e) => setDraftMarkdown(e.target.value)}
+                placeholder="Markdownで下書きを入力"
+              />
+              <textarea readOnly value={draftBacklog} placeholder="Backlog記法の変換結果" />
+            </div>
+          </section>
+
           <section className="panel">
             <div className="row between">
               <h2>エクスポート履歴</h2>
@@ -388,7 +1234,7 @@ export function App() {
                 {exports.map((x) => (
                   <li key={x.id} className="export-item">
                     <div>
-                      <strong>{x.issueKey}</strong> - {x.exportPath}
+                      <strong>{x.issueKey}</strong> - {x.error ? <span className="error">失敗: {x.error}</span> : x.exportPath}
                     </div>
                     <div className="subtle">{new Date(x.exportedAt).toLocaleString()}</div>
                   </li>
@@ -400,6 +1246,7 @@ export function App() {
       )}
 
       {loading && <p className="status">処理中...</p>}
+      {rateLimitMessage && <p className="subtle">{rateLimitMessage}</p>}
       {notice && <p className="status">{notice}</p>}
       {error && <p className="error">{error}</p>}
     </main>
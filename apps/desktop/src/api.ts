@@ -1,5 +1,26 @@
 import { invoke } from '@tauri-apps/api/tauri';
-import type { ExportHistory, IssueDetail, IssueSummary, Project, SetupState } from './types';
+import type {
+  BulkExportResult,
+  BulkExportTarget,
+  ConversionReport,
+  ExportHistory,
+  ExportResult,
+  ExportTemplate,
+  FrontMatterSettings,
+  FilterOptions,
+  IssueDetail,
+  IssueFilter,
+  IssueSearchPage,
+  IssueSummary,
+  Profile,
+  Project,
+  RateLimitStatus,
+  SetupAuth,
+  WikiExportResult,
+  WikiPage,
+  WikiSummary,
+  SetupState
+} from './types';
 
 function normalizeError(e: unknown): Error {
   if (typeof e === 'string') {
@@ -22,9 +43,10 @@ export async function setupLoad(): Promise<SetupState> {
   }
 }
 
-export async function setupSave(spaceUrl: string, apiKey: string): Promise<void> {
+// With OAuth this resolves once the user has approved access in the browser.
+export async function setupSave(spaceUrl: string, auth: SetupAuth): Promise<void> {
   try {
-    await invoke('setup_save', { spaceUrl, apiKey });
+    await invoke('setup_save', { spaceUrl, auth });
   } catch (e) {
     throw normalizeError(e);
   }
@@ -46,9 +68,25 @@ export async function searchByKey(issueKey: string): Promise<IssueSummary[]> {
   }
 }
 
-export async function searchByKeyword(keyword: string): Promise<IssueSummary[]> {
+export async function searchByKeyword(keyword: string, count?: number, offset = 0): Promise<IssueSearchPage> {
   try {
-    return await invoke<IssueSummary[]>('issues_search_by_keyword', { keyword });
+    return await invoke<IssueSearchPage>('issues_search_by_keyword', { keyword, count: count ?? null, offset });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function issuesSearch(filter: IssueFilter, count?: number, offset = 0): Promise<IssueSearchPage> {
+  try {
+    return await invoke<IssueSearchPage>('issues_search', { filter, count: count ?? null, offset });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function searchFilterOptions(projectId: number): Promise<FilterOptions> {
+  try {
+    return await invoke<FilterOptions>('search_filter_options', { projectId });
   } catch (e) {
     throw normalizeError(e);
   }
@@ -62,14 +100,82 @@ export async function issueGetDetail(issueKey: string): Promise<IssueDetail> {
   }
 }
 
-export async function issueExportMarkdown(issueKey: string, targetDir: string, overwrite: boolean): Promise<string> {
+export async function issuesConversionReport(issueKeys: string[]): Promise<ConversionReport[]> {
+  try {
+    return await invoke<ConversionReport[]>('issues_conversion_report', { issueKeys });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function issueExportMarkdown(
+  issueKey: string,
+  targetDir: string,
+  overwrite: boolean,
+  linkIssueFiles = false
+): Promise<ExportResult> {
   try {
-    const result = await invoke<{ path: string }>('issue_export_markdown', {
+    return await invoke<ExportResult>('issue_export_markdown', {
       issueKey,
       targetDir,
-      overwrite
+      overwrite,
+      linkIssueFiles
+    });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function issuesExportBulk(
+  target: BulkExportTarget,
+  targetDir: string,
+  overwrite: boolean,
+  linkIssueFiles = false
+): Promise<BulkExportResult> {
+  try {
+    return await invoke<BulkExportResult>('issues_export_bulk', {
+      target,
+      targetDir,
+      overwrite,
+      linkIssueFiles
     });
-    return result.path;
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function wikisList(projectKey: string): Promise<WikiSummary[]> {
+  try {
+    return await invoke<WikiSummary[]>('wikis_list', { projectKey });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function wikiGet(wikiId: number): Promise<WikiPage> {
+  try {
+    return await invoke<WikiPage>('wiki_get', { wikiId });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function wikisExport(
+  projectKey: string,
+  wikiIds: number[],
+  targetDir: string,
+  overwrite: boolean
+): Promise<WikiExportResult> {
+  try {
+    return await invoke<WikiExportResult>('wikis_export', { projectKey, wikiIds, targetDir, overwrite });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function markdownToBacklog(markdown: string, projectKey?: string): Promise<string> {
+  try {
+    return await invoke<string>('markdown_to_backlog', { markdown, projectKey: projectKey ?? null });
   } catch (e) {
     throw normalizeError(e);
   }
@@ -99,6 +205,14 @@ export async function authReset(): Promise<void> {
   }
 }
 
+export async function rateLimitStatus(): Promise<RateLimitStatus> {
+  try {
+    return await invoke<RateLimitStatus>('rate_limit_status');
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
 export async function setExportDir(exportDir: string): Promise<void> {
   try {
     await invoke('set_export_dir', { exportDir });
@@ -106,3 +220,67 @@ export async function setExportDir(exportDir: string): Promise<void> {
     throw normalizeError(e);
   }
 }
+
+export async function setFrontMatter(settings: FrontMatterSettings): Promise<void> {
+  try {
+    await invoke('set_front_matter', { settings });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function exportTemplateGet(): Promise<ExportTemplate> {
+  try {
+    return await invoke<ExportTemplate>('export_template_get');
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function setExportTemplate(template: string | null): Promise<void> {
+  try {
+    await invoke('set_export_template', { template });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function profilesList(): Promise<Profile[]> {
+  try {
+    return await invoke<Profile[]>('profiles_list');
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function profileCreate(name: string): Promise<Profile> {
+  try {
+    return await invoke<Profile>('profile_create', { name });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function profileRename(profileId: number, name: string): Promise<void> {
+  try {
+    await invoke('profile_rename', { profileId, name });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function profileSwitch(profileId: number): Promise<void> {
+  try {
+    await invoke('profile_switch', { profileId });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
+
+export async function profileDelete(profileId: number): Promise<void> {
+  try {
+    await invoke('profile_delete', { profileId });
+  } catch (e) {
+    throw normalizeError(e);
+  }
+}
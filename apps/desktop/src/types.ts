@@ -2,6 +2,7 @@ export type Project = {
   id: number;
   projectKey: string;
   name: string;
+  textFormattingRule: string;
   syncedAt: string;
 };
 
@@ -11,6 +12,42 @@ export type IssueSummary = {
   updatedAt: string;
 };
 
+export type IssueFilter = {
+  keyword?: string | null;
+  projectIds?: number[];
+  statusIds?: number[];
+  assigneeIds?: number[];
+  issueTypeIds?: number[];
+  categoryIds?: number[];
+  milestoneIds?: number[];
+  createdSince?: string | null;
+  createdUntil?: string | null;
+  updatedSince?: string | null;
+  updatedUntil?: string | null;
+  sort?: 'created' | 'updated' | 'dueDate';
+  order?: 'asc' | 'desc';
+};
+
+export type NamedId = {
+  id: number;
+  name: string;
+};
+
+export type FilterOptions = {
+  statuses: NamedId[];
+  assignees: NamedId[];
+  issueTypes: NamedId[];
+  categories: NamedId[];
+  milestones: NamedId[];
+};
+
+export type IssueSearchPage = {
+  issues: IssueSummary[];
+  // Only the first page carries the hit count; later pages keep it.
+  total: number | null;
+  offset: number;
+};
+
 export type IssueDetail = {
   issueKey: string;
   summary: string;
@@ -18,6 +55,35 @@ export type IssueDetail = {
   descriptionMd: string;
   updatedAt: string;
   syncedAt: string;
+  conversionWarnings: ConversionWarning[];
+  issueType: string | null;
+  status: string | null;
+  priority: string | null;
+  assignee: string | null;
+  createdUser: string | null;
+  createdAt: string | null;
+  dueDate: string | null;
+  parentIssueKey: string | null;
+  categories: string[];
+  versions: string[];
+  milestones: string[];
+};
+
+export type ConversionWarning = {
+  construct: string;
+  line: number;
+  action: string;
+};
+
+export type ConversionReport = {
+  issueKey: string;
+  summary: string;
+  warnings: ConversionWarning[];
+};
+
+export type ExportResult = {
+  path: string;
+  attachmentCount: number;
 };
 
 export type ExportHistory = {
@@ -25,12 +91,105 @@ export type ExportHistory = {
   issueKey: string;
   exportPath: string;
   exportedAt: string;
+  error: string | null;
 };
 
+export type BulkExportTarget =
+  | { kind: 'project'; projectKey: string }
+  | { kind: 'keys'; issueKeys: string[] }
+  | { kind: 'search'; keyword: string; projectKey: string | null };
+
+export type BulkExportItem = {
+  issueKey: string;
+  path: string | null;
+  attachmentCount: number;
+  error: string | null;
+};
+
+export type BulkExportResult = {
+  exported: number;
+  failed: number;
+  items: BulkExportItem[];
+};
+
+export type WikiSummary = {
+  id: number;
+  projectId: number;
+  name: string;
+  updatedAt: string;
+};
+
+export type WikiPage = {
+  id: number;
+  projectId: number;
+  name: string;
+  contentRaw: string;
+  contentMd: string;
+  tags: string[];
+  updatedAt: string;
+  syncedAt: string;
+  conversionWarnings: ConversionWarning[];
+};
+
+export type WikiExportItem = {
+  wikiId: number;
+  name: string;
+  path: string | null;
+  error: string | null;
+};
+
+export type WikiExportResult = {
+  exported: number;
+  failed: number;
+  items: WikiExportItem[];
+};
+
+export type AuthMethod = 'apiKey' | 'oauth';
+
+export type SetupAuth =
+  | { method: 'apiKey'; apiKey: string }
+  | { method: 'oauth'; clientId: string; clientSecret: string };
+
 export type SetupState = {
   spaceUrl?: string;
   hasApiKey: boolean;
+  authMethod: AuthMethod;
+  oauthRedirectUri: string;
   exportDir?: string;
+  frontMatter: FrontMatterSettings;
+};
+
+export type Profile = {
+  id: number;
+  name: string;
+  active: boolean;
+};
+
+export type RateLimitStatus = {
+  limit?: number;
+  remaining?: number;
+  resetAt?: string;
+  waitingUntil?: string;
+};
+
+export type FrontMatterField =
+  | 'issue_key'
+  | 'summary'
+  | 'status'
+  | 'assignee'
+  | 'url'
+  | 'updated_at'
+  | 'exported_at'
+  | 'tags';
+
+export type ExportTemplate = {
+  template: string;
+  isDefault: boolean;
+};
+
+export type FrontMatterSettings = {
+  enabled: boolean;
+  fields: FrontMatterField[];
 };
 
 export type AppError = {